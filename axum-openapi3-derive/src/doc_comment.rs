@@ -0,0 +1,105 @@
+/// Summary and description read from the `///` doc comments of a handler.
+#[derive(Debug, Default, PartialEq)]
+pub struct DocComment {
+    /// The first paragraph, on a single line.
+    pub summary: Option<String>,
+    /// The following paragraphs, as Markdown.
+    pub description: Option<String>,
+}
+
+/// Parse the `#[doc = "..."]` attributes. Non-literal docs (e.g. `include_str!`) are ignored.
+pub fn parse_doc_comment(attrs: &[syn::Attribute]) -> DocComment {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) => Some(lit.value()),
+            _ => None,
+        })
+        // A block comment is a single attribute with many lines
+        .flat_map(|doc| doc.split('\n').map(str::to_string).collect::<Vec<_>>())
+        .collect();
+
+    // `/// text` is desugared to `#[doc = " text"]`: strip the common indentation.
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default().trim_end())
+        .collect();
+
+    let mut paragraphs = lines
+        .split(|line| line.is_empty())
+        .skip_while(|p| p.is_empty());
+    let summary = paragraphs.next().map(|paragraph| {
+        paragraph
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+
+    let rest: Vec<&str> = lines
+        .iter()
+        .copied()
+        .skip_while(|line| line.is_empty())
+        .skip_while(|line| !line.is_empty())
+        .collect();
+    let description = rest.join("\n").trim_matches('\n').to_string();
+
+    DocComment {
+        summary,
+        description: (!description.is_empty()).then_some(description),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(item: &str) -> DocComment {
+        let item: syn::ItemFn = syn::parse_str(item).unwrap();
+        parse_doc_comment(&item.attrs)
+    }
+
+    #[test]
+    fn test_parse_doc_comment() {
+        assert_eq!(doc("fn f() {}"), DocComment::default());
+        assert_eq!(
+            doc("/// List all todos\nfn f() {}"),
+            DocComment {
+                summary: Some("List all todos".to_string()),
+                description: None,
+            }
+        );
+        assert_eq!(
+            doc("///\n/// List all\n/// todos\n///\n/// The todos are\n///   - sorted\n///\n/// by id\nfn f() {}"),
+            DocComment {
+                summary: Some("List all todos".to_string()),
+                description: Some("The todos are\n  - sorted\n\nby id".to_string()),
+            }
+        );
+        assert_eq!(
+            doc("/** List all todos\n\n Sorted by id */\nfn f() {}"),
+            DocComment {
+                summary: Some("List all todos".to_string()),
+                description: Some("Sorted by id".to_string()),
+            }
+        );
+        assert_eq!(
+            doc("#[doc = include_str!(\"README.md\")]\nfn f() {}"),
+            DocComment::default()
+        );
+    }
+}
@@ -1,13 +1,50 @@
-use syn::{spanned::Spanned, FnArg, Signature};
+use syn::{spanned::Spanned, FnArg, Signature, Token};
 
-use crate::util::recursive_type;
+use crate::util::{extractor_type, is_composite};
 
 #[derive(Debug)]
 pub enum HandlerArgument {
-    RequestBody(String),
-    Query(String),
-    State(String),
-    Path(String),
+    /// A JSON request body whose schema is built by the macro.
+    RequestBody(syn::Type),
+    Query(syn::Type),
+    State(syn::Type),
+    Path(syn::Type),
+    /// An `application/x-www-form-urlencoded` request body, from `#[openapi(form)]`.
+    Form(syn::Type),
+    /// A `multipart/form-data` request body, from `#[openapi(multipart = T)]`.
+    Multipart(syn::Type),
+    /// A `Form` of a `GET` or `HEAD` handler, which axum reads from the query string.
+    QueryForm(syn::Type),
+    /// An axum extractor whose documentation requires a derive on its inner type
+    /// (`Json`, `Form`, `Query`, `Multipart` and `TypedHeader`): its `OperationArg`
+    /// implementation is called directly, so a missing `ToSchema` or `IntoParams` fails to compile.
+    Known(syn::Type),
+    /// Any other argument, documented through the `OperationArg` trait if implemented.
+    Extractor(syn::Type),
+    /// An `Option` of an argument documented by the macro: its body and parameters are not required.
+    Optional(Box<HandlerArgument>),
+}
+
+impl HandlerArgument {
+    /// Read the `Form` arguments from the query string, as axum does for `GET` and `HEAD` requests.
+    pub fn form_as_query(self) -> Self {
+        match self {
+            HandlerArgument::Form(ty) => HandlerArgument::QueryForm(ty),
+            HandlerArgument::Known(ty) => {
+                let form = extractor_type(&ty)
+                    .filter(|(extractor, _)| extractor == "Form")
+                    .map(|(_, inner)| inner.clone());
+                match form {
+                    Some(inner) => HandlerArgument::QueryForm(inner),
+                    None => HandlerArgument::Known(ty),
+                }
+            }
+            HandlerArgument::Optional(inner) => {
+                HandlerArgument::Optional(Box::new(inner.form_as_query()))
+            }
+            argument => argument,
+        }
+    }
 }
 
 pub fn parse_handler_arguments(sig: &Signature) -> Result<Vec<HandlerArgument>, syn::Error> {
@@ -15,51 +52,18 @@ pub fn parse_handler_arguments(sig: &Signature) -> Result<Vec<HandlerArgument>,
     for input in &sig.inputs {
         match input {
             FnArg::Typed(pat_type) => {
-                let ty = &pat_type.ty;
-
-                match ty.as_ref() {
-                    syn::Type::Path(path) => {
-                        let mut v = vec![];
-                        recursive_type(path, &mut v);
-
-                        let extractor = v.remove(0);
-
-                        match extractor.as_str() {
-                            "Json" => {
-                                let l = v.len();
-
-                                ret.push(HandlerArgument::RequestBody(
-                                    v.join("<") + ">".repeat(l - 1).as_str(),
-                                ));
-                            }
-                            "Query" => {
-                                let l = v.len();
-
-                                ret.push(HandlerArgument::Query(
-                                    v.join("<") + ">".repeat(l - 1).as_str(),
-                                ));
-                            }
-                            "State" => {
-                                let l = v.len();
-
-                                ret.push(HandlerArgument::State(
-                                    v.join("<") + ">".repeat(l - 1).as_str(),
-                                ));
-                            }
-                            "Path" => {
-                                let l = v.len();
-
-                                ret.push(HandlerArgument::Path(
-                                    v.join("<") + ">".repeat(l - 1).as_str(),
-                                ));
-                            }
-                            _ => continue,
-                        };
-                    }
-                    _ => {
-                        return Err(syn::Error::new(ty.span(), "Expected Type::Path type"));
+                let ty = pat_type.ty.as_ref();
+
+                match parse_annotation(&pat_type.attrs, ty)? {
+                    Some(Annotation::Skip) => continue,
+                    Some(Annotation::As(argument)) => {
+                        ret.push(*argument);
+                        continue;
                     }
+                    None => {}
                 }
+
+                ret.push(parse_argument(ty));
             }
             _ => {
                 return Err(syn::Error::new(
@@ -73,22 +77,267 @@ pub fn parse_handler_arguments(sig: &Signature) -> Result<Vec<HandlerArgument>,
     Ok(ret)
 }
 
-pub fn parse_handler_ret_type(sig: &Signature) -> Result<Option<String>, syn::Error> {
+fn parse_argument(ty: &syn::Type) -> HandlerArgument {
+    match extractor_type(ty) {
+        Some((extractor, inner)) if extractor == "State" => HandlerArgument::State(inner.clone()),
+        Some((extractor, inner)) if extractor == "Path" => HandlerArgument::Path(inner.clone()),
+        // utoipa doesn't provide the schema of tuples and arrays,
+        // so `Json` of them is documented by the macro instead of through `OperationArg`.
+        Some((extractor, inner)) if extractor == "Json" && is_composite(inner) => {
+            HandlerArgument::RequestBody(inner.clone())
+        }
+        // Path parameters are always required, even if the extraction is optional.
+        // Other `Option`s are documented through `OperationArg`, unless the macro documents the inner type.
+        Some((extractor, inner)) if extractor == "Option" => match parse_argument(inner) {
+            HandlerArgument::Path(inner) => HandlerArgument::Path(inner),
+            HandlerArgument::Extractor(_) => HandlerArgument::Extractor(ty.clone()),
+            argument => HandlerArgument::Optional(Box::new(argument)),
+        },
+        _ if is_known_extractor(ty) => HandlerArgument::Known(ty.clone()),
+        _ => HandlerArgument::Extractor(ty.clone()),
+    }
+}
+
+/// Whether `ty` is an extractor documented by this crate, recognized by its last segment.
+fn is_known_extractor(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else {
+        return false;
+    };
+    path.path.segments.last().is_some_and(|segment| {
+        matches!(
+            segment.ident.to_string().as_str(),
+            "Json" | "Form" | "Query" | "Multipart" | "TypedHeader"
+        )
+    })
+}
+
+enum Annotation {
+    Skip,
+    As(Box<HandlerArgument>),
+}
+
+/// Parse the `#[openapi(...)]` attribute of an argument, used when the macro cannot
+/// recognize the extractor from its type (e.g. type aliases):
+/// - `#[openapi(body)]`, `#[openapi(query)]`, `#[openapi(path)]`, `#[openapi(state)]`
+///   and `#[openapi(form)]` treat the argument as `Json`, `Query`, `Path`, `State` and `Form` respectively.
+///   The inner type is the first generic argument of the argument type,
+///   or can be given explicitly, e.g. `#[openapi(body = Todo)]`.
+///   On an `Option` argument, the inner type is looked for in the wrapped type
+///   and the body or query parameters are not required.
+/// - `#[openapi(multipart = T)]` documents a `multipart/form-data` body with the parts of `T`.
+/// - `#[openapi(skip)]` doesn't document the argument.
+fn parse_annotation(
+    attrs: &[syn::Attribute],
+    ty: &syn::Type,
+) -> Result<Option<Annotation>, syn::Error> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("openapi")) else {
+        return Ok(None);
+    };
+
+    let (kind, inner) = attr.parse_args_with(|input: syn::parse::ParseStream| {
+        let kind: syn::Ident = input.parse()?;
+        let inner: Option<syn::Type> = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok((kind, inner))
+    })?;
+
+    if kind == "skip" {
+        return Ok(Some(Annotation::Skip));
+    }
+
+    let (optional, ty) = match extractor_type(ty) {
+        Some((extractor, inner)) if extractor == "Option" => (true, inner),
+        _ => (false, ty),
+    };
+
+    let inner = match inner {
+        Some(inner) => inner,
+        None => extractor_type(ty)
+            .map(|(_, inner)| inner.clone())
+            .ok_or_else(|| {
+                syn::Error::new(
+                    ty.span(),
+                    format!("Cannot infer the inner type, use `#[openapi({kind} = Type)]`"),
+                )
+            })?,
+    };
+
+    let argument = match kind.to_string().as_str() {
+        "body" => HandlerArgument::RequestBody(inner),
+        "query" => HandlerArgument::Query(inner),
+        "path" => HandlerArgument::Path(inner),
+        "state" => HandlerArgument::State(inner),
+        "form" => HandlerArgument::Form(inner),
+        "multipart" => HandlerArgument::Multipart(inner),
+        _ => {
+            return Err(syn::Error::new(
+                kind.span(),
+                "Expected one of `body`, `query`, `path`, `state`, `form`, `multipart` \
+                 or `skip`",
+            ))
+        }
+    };
+
+    let argument = match argument {
+        HandlerArgument::RequestBody(_)
+        | HandlerArgument::Query(_)
+        | HandlerArgument::Form(_)
+        | HandlerArgument::Multipart(_)
+            if optional =>
+        {
+            HandlerArgument::Optional(Box::new(argument))
+        }
+        argument => argument,
+    };
+
+    Ok(Some(Annotation::As(Box::new(argument))))
+}
+
+/// Remove the `#[openapi(...)]` attributes from the arguments, which are not valid Rust.
+pub fn strip_annotations(sig: &mut Signature) {
+    for input in sig.inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = input {
+            pat_type
+                .attrs
+                .retain(|attr| !attr.path().is_ident("openapi"));
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HandlerReturnType {
+    /// The type inside `Json`, documented as the success response.
+    pub json: Option<syn::Type>,
+    /// A raw body (e.g. `String` or `Bytes`), documented as the success response.
+    pub raw: Option<RawBody>,
+    /// Any other success type, documented by its `utoipa::IntoResponses` implementation if any.
+    pub responses: Option<syn::Type>,
+    /// The elements of a tuple return type before the body (e.g. typed headers),
+    /// which may document response headers through `ResponseHeaders`.
+    pub parts: Vec<syn::Type>,
+    /// The error type `E` of `Result<T, E>`.
+    pub error: Option<syn::Type>,
+}
+
+/// Body types which are not serialized, documented with their default content type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RawBody {
+    /// `String` and `&str`, served as `text/plain`.
+    Text,
+    /// `Bytes`, `Vec<u8>` and `Body`, served as `application/octet-stream`.
+    Binary,
+}
+
+impl RawBody {
+    /// Recognize a raw body type by its last segment.
+    pub fn from_type(ty: &syn::Type) -> Option<Self> {
+        let ty = match ty {
+            syn::Type::Reference(reference) => reference.elem.as_ref(),
+            ty => ty,
+        };
+        let syn::Type::Path(path) = ty else {
+            return None;
+        };
+        let segment = path.path.segments.last()?;
+        match segment.ident.to_string().as_str() {
+            "String" | "str" => Some(RawBody::Text),
+            "Bytes" | "Body" => Some(RawBody::Binary),
+            "Vec" => match extractor_type(ty) {
+                Some((_, syn::Type::Path(inner))) if inner.path.is_ident("u8") => {
+                    Some(RawBody::Binary)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RawBody::Text => "text/plain",
+            RawBody::Binary => "application/octet-stream",
+        }
+    }
+}
+
+pub fn parse_handler_ret_type(sig: &Signature) -> Result<HandlerReturnType, syn::Error> {
     match &sig.output {
         syn::ReturnType::Default => Err(syn::Error::new(sig.output.span(), "Expected return type")),
-        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
-            syn::Type::Path(path) => {
-                let mut v = vec![];
-                recursive_type(path, &mut v);
-
-                if v.remove(0) != "Json" {
-                    Ok(None)
-                } else {
-                    let l = v.len();
-                    Ok(Some(v.join("<") + ">".repeat(l - 1).as_str()))
-                }
+        syn::ReturnType::Type(_, ty) => Ok(parse_ret_type(ty)),
+    }
+}
+
+fn parse_ret_type(ty: &syn::Type) -> HandlerReturnType {
+    let path = match ty {
+        syn::Type::Path(path) => path,
+        // `(StatusCode, Json<T>)`, `(StatusCode, HeaderMap, Json<T>)`...:
+        // the last element is the body, the other ones only set the status and the headers
+        syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let mut elems: Vec<_> = tuple.elems.iter().collect();
+            let body = elems.pop().unwrap();
+            let parts = elems
+                .into_iter()
+                .filter(|ty| !matches!(ty, syn::Type::ImplTrait(_) | syn::Type::Infer(_)))
+                .cloned()
+                .collect();
+            return HandlerReturnType {
+                parts,
+                ..parse_ret_type(body)
+            };
+        }
+        syn::Type::Paren(paren) => return parse_ret_type(&paren.elem),
+        ty => return other_ret_type(ty),
+    };
+
+    let Some(segment) = path.path.segments.last() else {
+        return HandlerReturnType::default();
+    };
+    if segment.ident == "Result" {
+        let mut args = match &segment.arguments {
+            syn::PathArguments::AngleBracketed(args) => {
+                args.args.iter().filter_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
             }
-            _ => Ok(None),
+            _ => return HandlerReturnType::default(),
+        };
+
+        let ok = args.next().map(parse_ret_type).unwrap_or_default();
+        return HandlerReturnType {
+            error: args.next().cloned(),
+            ..ok
+        };
+    }
+
+    match extractor_type(ty) {
+        Some((extractor, inner)) if extractor == "Json" => HandlerReturnType {
+            json: Some(inner.clone()),
+            ..Default::default()
         },
+        _ => other_ret_type(ty),
+    }
+}
+
+/// A raw body, or a type which may implement `IntoResponses`.
+fn other_ret_type(ty: &syn::Type) -> HandlerReturnType {
+    if let Some(raw) = RawBody::from_type(ty) {
+        return HandlerReturnType {
+            raw: Some(raw),
+            ..Default::default()
+        };
+    }
+    // `impl IntoResponse` cannot be named, so it's not documented
+    let responses = match ty {
+        syn::Type::ImplTrait(_) | syn::Type::Infer(_) | syn::Type::Never(_) => None,
+        ty => Some(ty.clone()),
+    };
+    HandlerReturnType {
+        responses,
+        ..Default::default()
     }
 }
@@ -1,14 +1,21 @@
 //! Derive macro for defining an endpoint.
 //! See [`axum_openapi3`](https://crates.io/crates/axum-openapi3) for more information.
 
-use handler_signature::{parse_handler_arguments, parse_handler_ret_type, HandlerArgument};
-use macro_arguments::MacroArgs;
+use doc_comment::parse_doc_comment;
+use handler_signature::{
+    parse_handler_arguments, parse_handler_ret_type, strip_annotations, HandlerArgument,
+    HandlerReturnType, RawBody,
+};
+use macro_arguments::{MacroArgs, ParamArg, SecurityRequirement};
+use path_template::{extract_params, openapi_path, validate_path, validate_path_extractors};
 use quote::quote;
-use std::fmt::Write;
 use syn::{parse_macro_input, spanned::Spanned, ItemFn};
+use util::{extractor_type, schema_token, schemas_token};
 
+mod doc_comment;
 mod handler_signature;
 mod macro_arguments;
+mod path_template;
 mod util;
 
 /// Derive macro for defining an endpoint.
@@ -18,24 +25,66 @@ pub fn endpoint(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let macro_args = parse_macro_input!(args as MacroArgs);
-    let input_fn = parse_macro_input!(input as ItemFn);
+    let mut input_fn = parse_macro_input!(input as ItemFn);
 
     let fn_args = match parse_handler_arguments(&input_fn.sig) {
         Ok(args) => args,
         Err(err) => return err.to_compile_error().into(),
     };
 
+    let fn_args: Vec<_> = if matches!(macro_args.method, http::Method::GET | http::Method::HEAD) {
+        fn_args
+            .into_iter()
+            .map(HandlerArgument::form_as_query)
+            .collect()
+    } else {
+        fn_args
+    };
+
     let ret_type = match parse_handler_ret_type(&input_fn.sig) {
         Ok(ty) => ty,
         Err(err) => return err.to_compile_error().into(),
     };
 
+    strip_annotations(&mut input_fn.sig);
+
     let fn_name = input_fn.sig.ident.clone();
-    let fn_name_str = fn_name.to_string();
+    let operation_id = macro_args
+        .operation_id
+        .unwrap_or_else(|| fn_name.to_string());
 
     let path = macro_args.path;
+    if let Err(err) = validate_path(&path) {
+        return syn::Error::new(macro_args.path_span, err)
+            .to_compile_error()
+            .into();
+    }
+    // The placeholders of the nest prefix are extracted before the ones of the path
+    let mut path_param_names = vec![];
+    if let Some((prefix, prefix_span)) = &macro_args.path_prefix {
+        let full_path = format!("{}{path}", prefix.trim_end_matches('/'));
+        if let Err(err) = validate_path(prefix).and_then(|_| validate_path(&full_path)) {
+            return syn::Error::new(*prefix_span, err).to_compile_error().into();
+        }
+        path_param_names = extract_params(prefix);
+    }
+    path_param_names.extend(extract_params(&path));
+    // The route keeps the axum syntax, the spec uses the OpenAPI one
+    let spec_path = openapi_path(&path);
     let method = macro_args.method.to_string();
-    let description = macro_args.description.unwrap_or_default();
+    // Explicit arguments take precedence over the doc comments
+    let doc_comment = parse_doc_comment(&input_fn.attrs);
+    let description = macro_args
+        .description
+        .or(doc_comment.description)
+        .unwrap_or_default();
+    let summary = macro_args.summary.or(doc_comment.summary);
+    // Keep the doc comments on the generated function, for rustdoc
+    let docs: Vec<_> = input_fn
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .collect();
 
     let method: http::Method = method.parse().unwrap(); //The HTTP method parsing fails before
 
@@ -48,23 +97,42 @@ pub fn endpoint(
         }
     };
 
-    let ret_type = get_ret_type_token(ret_type);
-
-    let request_body = get_request_body_token(&fn_args);
-
-    let query_params = get_query_params_token(&fn_args);
-
-    let path_param_names = extract_params(&path);
+    let error_responses = get_error_responses_token(&ret_type);
+    let extra_schemas = get_extra_schemas_token(&macro_args.schemas);
+    let response_headers = get_response_headers_token(&ret_type, macro_args.response_headers);
+    let ret_type_for_schemas = ret_type.json.clone();
+    let status = macro_args.status.unwrap_or(200).to_string();
+    let ret_type = get_ret_type_token(
+        &ret_type,
+        &status,
+        macro_args.status.is_some(),
+        macro_args.response_content_type,
+    );
+    let content_type = get_content_type_token(macro_args.content_type);
+    let operation_info = get_operation_info_token(summary, macro_args.tags, macro_args.deprecated);
+    let security = get_security_token(macro_args.security);
+
+    let extractors = get_extractors_token(&fn_args);
+    let rejections = macro_args
+        .rejections
+        .then(|| get_rejections_token(&fn_args));
+
+    if let Err(err) = validate_path_extractors(&fn_args, &path_param_names, macro_args.path_span) {
+        return err.to_compile_error().into();
+    }
     let path_params = get_path_params_token(&fn_args, path_param_names);
+    let header_params = get_params_token(macro_args.headers, quote! { Header });
+    let cookie_params = get_params_token(macro_args.cookies, quote! { Cookie });
 
-    let state = get_state_token(fn_args);
+    let schemas = get_schemas_token(ret_type_for_schemas);
 
-    let path_for_openapi = transform_route(&path);
+    let state = get_state_token(fn_args);
 
     let public = get_public_token(&input_fn.vis);
 
     let output = quote! {
-        #public fn #fn_name() -> (&'static str, axum::routing::MethodRouter < #state , std::convert::Infallible >)
+        #(#docs)*
+        #public fn #fn_name() -> axum_openapi3::Endpoint < #state >
         {
             #input_fn
 
@@ -73,28 +141,57 @@ pub fn endpoint(
             let op_builder = axum_openapi3::utoipa::openapi::path::OperationBuilder::new()
                 .description(Some(#description));
 
+            #operation_info
+
+            #security
+
+            let mut schemas: Vec<(String, axum_openapi3::utoipa::openapi::RefOr<axum_openapi3::utoipa::openapi::schema::Schema>)> = vec![];
+
             #ret_type
 
-            #request_body
+            #error_responses
 
-            #query_params
+            #extra_schemas
 
             #path_params
 
-            let op_builder = op_builder.operation_id(Some(#fn_name_str));
+            #header_params
+
+            #cookie_params
+
+            let op_builder = op_builder.operation_id(Some(#operation_id));
+
+            #schemas
+
+            let mut operation = op_builder.build();
+
+            #extractors
+
+            #rejections
+
+            #content_type
+
+            #response_headers
 
             let paths = axum_openapi3::utoipa::openapi::PathsBuilder::new()
-                .path(#path_for_openapi, axum_openapi3::utoipa::openapi::path::PathItemBuilder::new()
+                .path(#spec_path, axum_openapi3::utoipa::openapi::path::PathItemBuilder::new()
                     .operation(
                         axum_openapi3::utoipa::openapi::HttpMethod:: #utoipa_method_name,
-                        op_builder.build()
+                        operation
                     )
                     .build())
                 .build();
 
-            axum_openapi3::ENDPOINTS.lock().unwrap().push(paths);
+            let openapi = axum_openapi3::utoipa::openapi::OpenApiBuilder::new()
+                .paths(paths)
+                .components(Some(components))
+                .build();
 
-            (#path, handler)
+            axum_openapi3::Endpoint {
+                path: #path,
+                method_router: handler,
+                openapi,
+            }
         }
 
     };
@@ -102,69 +199,302 @@ pub fn endpoint(
     output.into()
 }
 
-fn get_ret_type_token(ret_type: Option<String>) -> proc_macro2::TokenStream {
-    let ret_type = if let Some(ret_type) = ret_type {
-        format!(
-            r#"
-let response_schema = < {ret_type} as axum_openapi3::utoipa::PartialSchema > :: schema();
-let op_builder = op_builder.response(
-    "200", 
-    axum_openapi3::utoipa::openapi::ResponseBuilder::new()
-        .content(
-            "application/json", 
-            axum_openapi3::utoipa::openapi::ContentBuilder::new()
-                .schema(Some(response_schema))
-                .build()
-        )
-        .build()
-);
-            "#
-        )
-    } else {
-        "let op_builder = op_builder;".to_string()
+fn get_operation_info_token(
+    summary: Option<String>,
+    tags: Vec<String>,
+    deprecated: bool,
+) -> proc_macro2::TokenStream {
+    let summary = summary.map(|summary| {
+        quote! { let op_builder = op_builder.summary(Some(#summary)); }
+    });
+    let tags = (!tags.is_empty()).then(|| {
+        quote! { let op_builder = op_builder.tags(Some(vec![#(#tags),*])); }
+    });
+    let deprecated = deprecated.then(|| {
+        quote! {
+            let op_builder = op_builder
+                .deprecated(Some(axum_openapi3::utoipa::openapi::Deprecated::True));
+        }
+    });
+    quote! {
+        #summary
+        #tags
+        #deprecated
+    }
+}
+
+fn get_security_token(security: Option<Vec<SecurityRequirement>>) -> proc_macro2::TokenStream {
+    let Some(security) = security else {
+        return quote! {};
     };
-    let ret_type: proc_macro2::TokenStream = ret_type.parse().unwrap();
-    ret_type
+    let requirements = security.into_iter().map(|requirement| {
+        let schemes = requirement.into_iter().map(|(name, scopes)| {
+            quote! { .add(#name, ::std::vec::Vec::<&str>::from([#(#scopes),*])) }
+        });
+        quote! {
+            axum_openapi3::utoipa::openapi::security::SecurityRequirement::default() #(#schemes)*
+        }
+    });
+    quote! {
+        let op_builder = op_builder.securities(Some(vec![#(#requirements),*]));
+    }
 }
 
-fn get_request_body_token(fn_args: &[HandlerArgument]) -> proc_macro2::TokenStream {
-    let request_body = fn_args.iter().find_map(|arg| match arg {
-        HandlerArgument::RequestBody(ty) => Some(format!(
-            r#"
-let request_body = < {ty} as axum_openapi3::utoipa::PartialSchema > :: schema();
-let op_builder = op_builder
-        .request_body(Some(
-            axum_openapi3::utoipa::openapi::request_body::RequestBodyBuilder::new()
+/// The success responses of a type implementing `IntoResponses`,
+/// or the success response with the `Json` or raw body if any.
+/// Without a body, the response is documented only if the status or the content type is explicit.
+fn get_ret_type_token(
+    ret_type: &HandlerReturnType,
+    status: &str,
+    explicit_status: bool,
+    content_type: Option<String>,
+) -> proc_macro2::TokenStream {
+    let response = get_response_token(ret_type, status, explicit_status, content_type);
+    let Some(ty) = &ret_type.responses else {
+        return response;
+    };
+
+    quote! {
+        #[allow(unused_imports)]
+        use axum_openapi3::__private::{IntoResponsesFallback as _, IntoResponsesViaTrait as _};
+        let op_builder = match (&&axum_openapi3::__private::Wrap::< #ty >::new()).responses() {
+            Some(responses) => responses
+                .into_iter()
+                .fold(op_builder, |op_builder, (status, response)| op_builder.response(status, response)),
+            None => {
+                #response
+                op_builder
+            }
+        };
+    }
+}
+
+fn get_response_token(
+    ret_type: &HandlerReturnType,
+    status: &str,
+    explicit_status: bool,
+    content_type: Option<String>,
+) -> proc_macro2::TokenStream {
+    let body = match (&ret_type.json, ret_type.raw) {
+        (Some(json), _) => Some(("application/json", schema_token(json))),
+        (None, Some(raw)) => Some((raw.content_type(), raw_schema_token(raw))),
+        (None, None) => None,
+    };
+
+    let content = match (body, content_type) {
+        (Some((default_content_type, schema)), content_type) => {
+            let content_type = content_type.unwrap_or_else(|| default_content_type.to_string());
+            quote! {
                 .content(
-                    "application/json",
+                    #content_type,
                     axum_openapi3::utoipa::openapi::ContentBuilder::new()
-                        .schema(Some(request_body))
+                        .schema(Some(#schema))
                         .build()
                 )
+            }
+        }
+        (None, Some(content_type)) => quote! {
+            .content(
+                #content_type,
+                axum_openapi3::utoipa::openapi::ContentBuilder::new().build()
+            )
+        },
+        (None, None) if explicit_status => quote! {},
+        (None, None) => return quote! { let op_builder = op_builder; },
+    };
+
+    quote! {
+        let op_builder = op_builder.response(
+            #status,
+            axum_openapi3::utoipa::openapi::ResponseBuilder::new()
+                #content
                 .build()
-        ));
-            "#
-        )),
-        _ => None,
-    });
-    let request_body: proc_macro2::TokenStream = if let Some(request_body) = request_body {
-        request_body.parse().unwrap()
-    } else {
-        "let op_builder = op_builder;".parse().unwrap()
+        );
+    }
+}
+
+fn raw_schema_token(raw: RawBody) -> proc_macro2::TokenStream {
+    match raw {
+        RawBody::Text => quote! {
+            <String as axum_openapi3::utoipa::PartialSchema>::schema()
+        },
+        RawBody::Binary => quote! { axum_openapi3::__private::binary_schema() },
+    }
+}
+
+/// Override the content type of the request body.
+fn get_content_type_token(content_type: Option<String>) -> proc_macro2::TokenStream {
+    let Some(content_type) = content_type else {
+        return quote! {};
     };
-    request_body
+    quote! {
+        axum_openapi3::__private::set_request_content_type(&mut operation, #content_type);
+    }
+}
+
+fn get_error_responses_token(ret_type: &HandlerReturnType) -> proc_macro2::TokenStream {
+    let Some(error) = &ret_type.error else {
+        return quote! { let op_builder = op_builder; };
+    };
+
+    quote! {
+        #[allow(unused_imports)]
+        use axum_openapi3::__private::{ErrorResponsesFallback as _, ErrorResponsesViaTrait as _};
+        let error = axum_openapi3::__private::Wrap::< #error >::new();
+        #[allow(unused_imports)]
+        use axum_openapi3::__private::{IntoResponsesFallback as _, IntoResponsesViaTrait as _};
+        let op_builder = (&&error)
+            .error_responses()
+            .into_iter()
+            .chain((&&error).responses().unwrap_or_default())
+            .fold(op_builder, |op_builder, (status, response)| op_builder.response(status, response));
+        (&&error).error_schemas(&mut schemas);
+    }
+}
+
+/// The schemas declared by `schemas(...)`, with the schemas they reference.
+fn get_extra_schemas_token(types: &[syn::Type]) -> proc_macro2::TokenStream {
+    let schemas = types.iter().map(|ty| {
+        let references = schemas_token(ty);
+        quote! {
+            schemas.push((
+                < #ty as axum_openapi3::utoipa::ToSchema >::name().into_owned(),
+                < #ty as axum_openapi3::utoipa::PartialSchema >::schema(),
+            ));
+            #references
+        }
+    });
+    quote! { #(#schemas)* }
+}
+
+fn get_schemas_token(ret_type: Option<syn::Type>) -> proc_macro2::TokenStream {
+    // Collect the schemas referenced by the response type,
+    // so `$ref`s emitted by utoipa point to an existing component.
+    let schemas = ret_type.map(|ty| schemas_token(&ty));
+
+    quote! {
+        #schemas
+        let mut components = axum_openapi3::utoipa::openapi::ComponentsBuilder::new()
+            .schemas_from_iter(schemas)
+            .build();
+    }
+}
+
+fn get_extractors_token(fn_args: &[HandlerArgument]) -> proc_macro2::TokenStream {
+    let extractors = fn_args.iter().filter_map(get_extractor_token);
+
+    quote! {
+        #[allow(unused_imports)]
+        use axum_openapi3::__private::{OperationArgFallback as _, OperationArgViaTrait as _};
+        #(#extractors)*
+    }
+}
+
+fn get_extractor_token(arg: &HandlerArgument) -> Option<proc_macro2::TokenStream> {
+    match arg {
+        HandlerArgument::RequestBody(ty) => {
+            let request_body = schema_token(ty);
+            let schemas = schemas_token(ty);
+            Some(quote! {
+                let mut schemas = vec![];
+                #schemas
+                for (name, schema) in schemas {
+                    components.schemas.entry(name).or_insert(schema);
+                }
+                operation.request_body = Some(
+                    axum_openapi3::utoipa::openapi::request_body::RequestBodyBuilder::new()
+                        .content(
+                            "application/json",
+                            axum_openapi3::utoipa::openapi::ContentBuilder::new()
+                                .schema(Some(#request_body))
+                                .build()
+                        )
+                        .required(Some(axum_openapi3::utoipa::openapi::Required::True))
+                        .build()
+                );
+            })
+        }
+        HandlerArgument::Query(ty) => Some(quote! {
+            operation
+                .parameters
+                .get_or_insert_with(Vec::new)
+                .extend(< #ty as axum_openapi3::utoipa::IntoParams >::into_params(|| {
+                    Some(axum_openapi3::utoipa::openapi::path::ParameterIn::Query)
+                }));
+        }),
+        HandlerArgument::Form(ty) => Some(quote! {
+            axum_openapi3::__private::form_body::< #ty >(&mut operation, &mut components);
+        }),
+        HandlerArgument::QueryForm(ty) => Some(quote! {
+            axum_openapi3::__private::form_query::< #ty >(&mut operation, &mut components);
+        }),
+        HandlerArgument::Multipart(ty) => Some(quote! {
+            #[allow(unused_imports)]
+            use axum_openapi3::__private::{MultipartFormFallback as _, MultipartFormViaTrait as _};
+            let encodings = (&&axum_openapi3::__private::Wrap::< #ty >::new()).multipart_encodings();
+            axum_openapi3::__private::multipart_body::< #ty >(&mut operation, &mut components, encodings);
+        }),
+        HandlerArgument::Known(ty) => Some(quote! {
+            < #ty as axum_openapi3::OperationArg >::document(&mut operation, &mut components);
+        }),
+        HandlerArgument::Extractor(ty) => Some(quote! {
+            (&&axum_openapi3::__private::Wrap::< #ty >::new())
+                .document(&mut operation, &mut components);
+        }),
+        HandlerArgument::Optional(inner) => {
+            let inner = get_extractor_token(inner)?;
+            Some(quote! {
+                let optional = axum_openapi3::__private::OptionalArg::before(&operation);
+                #inner
+                optional.after(&mut operation);
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The responses returned when the extractors reject the request, for `rejections = true`.
+/// The arguments recognized by the macro are documented as the corresponding axum extractor.
+fn get_rejections_token(fn_args: &[HandlerArgument]) -> proc_macro2::TokenStream {
+    fn extractor(arg: &HandlerArgument) -> Option<proc_macro2::TokenStream> {
+        match arg {
+            HandlerArgument::RequestBody(ty) => Some(quote! { axum::Json< #ty > }),
+            HandlerArgument::Query(ty) | HandlerArgument::QueryForm(ty) => {
+                Some(quote! { axum::extract::Query< #ty > })
+            }
+            HandlerArgument::Path(ty) => Some(quote! { axum::extract::Path< #ty > }),
+            HandlerArgument::Form(ty) => Some(quote! { axum::Form< #ty > }),
+            HandlerArgument::Multipart(_) => {
+                Some(quote! { axum_openapi3::__private::MultipartRejections })
+            }
+            HandlerArgument::Known(ty) | HandlerArgument::Extractor(ty) => Some(quote! { #ty }),
+            HandlerArgument::Optional(inner) => {
+                let inner = extractor(inner)?;
+                Some(quote! { Option< #inner > })
+            }
+            HandlerArgument::State(_) => None,
+        }
+    }
+    let extractors = fn_args.iter().filter_map(extractor);
+
+    quote! {
+        #[allow(unused_imports)]
+        use axum_openapi3::__private::{RejectionResponsesFallback as _, RejectionResponsesViaTrait as _};
+        let mut rejections = Vec::new();
+        #(rejections.extend((&&axum_openapi3::__private::Wrap::< #extractors >::new()).rejections());)*
+        axum_openapi3::__private::add_rejection_responses(&mut operation, &mut components, rejections);
+    }
 }
 
 fn get_state_token(fn_args: Vec<HandlerArgument>) -> proc_macro2::TokenStream {
-    let state = fn_args
-        .iter()
-        .find_map(|arg| match arg {
-            HandlerArgument::State(ty) => Some(ty.as_str()),
-            _ => None,
-        })
-        .unwrap_or("()");
-    let state: proc_macro2::TokenStream = state.parse().unwrap();
-    state
+    let state = fn_args.into_iter().find_map(|arg| match arg {
+        HandlerArgument::State(ty) => Some(ty),
+        _ => None,
+    });
+    match state {
+        Some(state) => quote! { #state },
+        None => quote! { () },
+    }
 }
 
 fn get_public_token(public: &syn::Visibility) -> proc_macro2::TokenStream {
@@ -179,56 +509,110 @@ fn get_path_params_token(
     fn_args: &[HandlerArgument],
     path_param_names: Vec<String>,
 ) -> proc_macro2::TokenStream {
-    let path_params: String = fn_args
+    let path_params = fn_args
         .iter()
         .filter_map(|arg| match arg {
             HandlerArgument::Path(ty) => Some(ty),
             _ => None,
         })
-        .zip(path_param_names.iter())
-        .fold(String::new(), |mut acc, (ty, name)| {
-            let _ = write!(
-                acc,
-                r#"
-let schema = < {ty} as axum_openapi3::utoipa::PartialSchema > :: schema();
-let path_param = axum_openapi3::utoipa::openapi::path::ParameterBuilder::new()
-    .parameter_in(axum_openapi3::utoipa::openapi::path::ParameterIn::Path)
-    .name("{name}")
-    .required(axum_openapi3::utoipa::openapi::Required::True)
-    .schema(Some(schema))
-    .build();
-
-let op_builder = op_builder
-    .parameter(path_param);
-"#
-            );
-            acc
+        .map(|ty| match ty {
+            // Tuples are split element by element onto the path template names.
+            syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+                let params = tuple.elems.iter().zip(path_param_names.iter()).map(|(ty, name)| {
+                    let schema = schema_token(ty);
+                    let schemas = schemas_token(ty);
+                    quote! {
+                        #schemas
+                        let op_builder = op_builder
+                            .parameter(axum_openapi3::__private::path_param(#name, #schema));
+                    }
+                });
+                quote! { #(#params)* }
+            }
+            _ => quote! {
+                #[allow(unused_imports)]
+                use axum_openapi3::__private::{PathParamsViaIntoParams as _, PathParamsViaSchema as _};
+                let op_builder = op_builder.parameters(Some(
+                    (&&axum_openapi3::__private::Wrap::< #ty >::new())
+                        .path_params(&[#(#path_param_names),*], &mut schemas)
+                ));
+            },
         });
-    let path_params: proc_macro2::TokenStream = if path_params.is_empty() {
-        "let op_builder = op_builder;".parse().unwrap()
-    } else {
-        path_params.parse().unwrap()
-    };
-    path_params
+
+    quote! {
+        #(#path_params)*
+    }
 }
 
-fn get_query_params_token(fn_args: &[HandlerArgument]) -> proc_macro2::TokenStream {
-    let query_params = fn_args.iter().find_map(|arg| {
-        match arg {
-            HandlerArgument::Query(ty) => Some(format!(r#"
-let query_params = < {ty} as axum_openapi3::utoipa::IntoParams > :: into_params(|| Some(axum_openapi3::utoipa::openapi::path::ParameterIn::Query));
-let op_builder = op_builder
-    .parameters(Some(query_params));
-            "#)),
-            _ => None,
+/// Parameters declared by the `headers(...)` and `cookies(...)` arguments.
+/// `Option<T>` values are documented as not required, with the schema of `T`.
+fn get_params_token(
+    params: Vec<ParamArg>,
+    parameter_in: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let params = params.into_iter().map(|param| {
+        let name = param.name;
+        let ty = param
+            .ty
+            .unwrap_or_else(|| syn::parse_quote! { ::std::string::String });
+        let (ty, required) = match extractor_type(&ty) {
+            Some((outer, inner)) if outer == "Option" => (inner.clone(), false),
+            _ => (ty, true),
+        };
+        let schema = schema_token(&ty);
+        let schemas = schemas_token(&ty);
+        quote! {
+            #schemas
+            let op_builder = op_builder.parameter(axum_openapi3::__private::param(
+                #name,
+                axum_openapi3::utoipa::openapi::path::ParameterIn:: #parameter_in,
+                #required,
+                #schema,
+            ));
         }
     });
-    let query_params: proc_macro2::TokenStream = if let Some(query_params) = query_params {
-        query_params.parse().unwrap()
-    } else {
-        "let op_builder = op_builder;".parse().unwrap()
-    };
-    query_params
+    quote! { #(#params)* }
+}
+
+/// Headers of the success responses, declared by `response_headers(...)`
+/// or by the types implementing `ResponseHeaders` in the return type.
+fn get_response_headers_token(
+    ret_type: &HandlerReturnType,
+    headers: Vec<ParamArg>,
+) -> proc_macro2::TokenStream {
+    let types: Vec<_> = ret_type.parts.iter().chain(&ret_type.responses).collect();
+    if types.is_empty() && headers.is_empty() {
+        return quote! {};
+    }
+
+    let headers = headers.into_iter().map(|header| {
+        let name = header.name;
+        let ty = header
+            .ty
+            .unwrap_or_else(|| syn::parse_quote! { ::std::string::String });
+        let schema = schema_token(&ty);
+        let schemas = schemas_token(&ty);
+        quote! {
+            #schemas
+            response_headers.insert(
+                #name.to_string(),
+                axum_openapi3::utoipa::openapi::HeaderBuilder::new().schema(#schema).build(),
+            );
+        }
+    });
+
+    quote! {
+        #[allow(unused_imports)]
+        use axum_openapi3::__private::{ResponseHeadersFallback as _, ResponseHeadersViaTrait as _};
+        let mut schemas = vec![];
+        let mut response_headers = std::collections::BTreeMap::new();
+        #(response_headers.extend((&&axum_openapi3::__private::Wrap::< #types >::new()).response_headers());)*
+        #(#headers)*
+        for (name, schema) in schemas {
+            components.schemas.entry(name).or_insert(schema);
+        }
+        axum_openapi3::__private::add_response_headers(&mut operation, response_headers);
+    }
 }
 
 fn get_method_tokens(
@@ -249,73 +633,3 @@ fn get_method_tokens(
         };
     Ok((utoipa_method_name, axum_method))
 }
-
-fn extract_params(input: &str) -> Vec<String> {
-    input
-        .split('/')
-        .filter_map(|segment| {
-            if segment.starts_with('{') && segment.ends_with("}") {
-                Some(
-                    segment
-                        .trim_start_matches('{')
-                        .trim_end_matches("}")
-                        .to_string(),
-                )
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
-fn transform_route(route: &str) -> String {
-    route
-        .split('/') // Split the route by '/'
-        .map(|segment| {
-            if let Some(stripped) = segment.strip_prefix(':') {
-                format!("{{{}}}", stripped) // Replace ':id' with '{id}'
-            } else {
-                segment.to_string() // Keep other segments unchanged
-            }
-        })
-        .collect::<Vec<_>>() // Collect transformed segments
-        .join("/") // Rejoin segments into a single string
-}
-
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_extract_params() {
-        assert_eq!(super::extract_params("/foo/{id}/bar"), vec!["id"]);
-        assert_eq!(
-            super::extract_params("/foo/{id}/bar/{baz}"),
-            vec!["id", "baz"]
-        );
-        assert_eq!(
-            super::extract_params("/foo/{id}/bar/{baz}/"),
-            vec!["id", "baz"]
-        );
-        assert_eq!(
-            super::extract_params("/foo/{id}/bar/{baz}/{qux}"),
-            vec!["id", "baz", "qux"]
-        );
-    }
-
-    #[test]
-    fn test_transform_route() {
-        let routes = vec![
-            ("/todos", "/todos"),
-            ("/todos/:id", "/todos/{id}"),
-            ("/todos/:id/foo", "/todos/{id}/foo"),
-            ("/bar/:bar_id/foo/:foo_id", "/bar/{bar_id}/foo/{foo_id}"),
-            (
-                "/bar/{bar_id}/foo/{foo_id}/baz",
-                "/bar/{bar_id}/foo/{foo_id}/baz",
-            ),
-        ];
-
-        for (input, expected) in routes {
-            assert_eq!(super::transform_route(input), expected);
-        }
-    }
-}
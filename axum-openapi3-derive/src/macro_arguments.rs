@@ -1,68 +1,153 @@
-use syn::{parse::Parse, spanned::Spanned, Expr, Lit, MetaNameValue, Token};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Expr, Lit, Meta, MetaNameValue, Token,
+};
 
 #[derive(Debug)]
 pub struct MacroArgs {
     pub method: http::Method,
     pub path: String,
+    pub path_span: proc_macro2::Span,
+    /// The path of the router the endpoint is nested in, e.g. `/orgs/{org_id}`:
+    /// its placeholders are extracted by the `Path` extractors too.
+    pub path_prefix: Option<(String, proc_macro2::Span)>,
     pub description: Option<String>,
+    pub status: Option<u16>,
+    pub summary: Option<String>,
+    pub tags: Vec<String>,
+    pub deprecated: bool,
+    pub operation_id: Option<String>,
+    /// The security requirements: any of them must be satisfied.
+    /// `Some(vec![])` documents an endpoint without security, overriding the router default.
+    pub security: Option<Vec<SecurityRequirement>>,
+    /// Override of the content type of the request body.
+    pub content_type: Option<String>,
+    /// Override of the content type of the success response.
+    pub response_content_type: Option<String>,
+    /// The header parameters declared by `headers(...)`.
+    pub headers: Vec<ParamArg>,
+    /// The cookie parameters declared by `cookies(...)`.
+    pub cookies: Vec<ParamArg>,
+    /// The headers of the success responses declared by `response_headers(...)`.
+    pub response_headers: Vec<ParamArg>,
+    /// Document the responses returned when an extractor rejects the request.
+    pub rejections: bool,
+    /// Types whose schemas are added to the components, declared by `schemas(...)`,
+    /// e.g. the bodies referenced by a return type implementing `IntoResponses`.
+    pub schemas: Vec<syn::Type>,
 }
+
+/// The security schemes, with their scopes, which must be all satisfied.
+pub type SecurityRequirement = Vec<(String, Vec<String>)>;
+
+/// A header or cookie parameter, or a response header: `"X-Tenant-Id"` or `"X-Tenant-Id": u64`.
+#[derive(Debug)]
+pub struct ParamArg {
+    pub name: String,
+    /// The type of the value, `String` if not given.
+    pub ty: Option<syn::Type>,
+}
+impl Parse for ParamArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: syn::LitStr = input.parse()?;
+        let ty = if input.peek(Token![:]) {
+            let _: Token![:] = input.parse()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(ParamArg {
+            name: name.value(),
+            ty,
+        })
+    }
+}
+
 impl Parse for MacroArgs {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut method = None;
         let mut path = None;
+        let mut path_span = None;
+        let mut path_prefix = None;
         let mut description = None;
+        let mut status = None;
+        let mut summary = None;
+        let mut tags = vec![];
+        let mut deprecated = false;
+        let mut operation_id = None;
+        let mut security = None;
+        let mut content_type = None;
+        let mut response_content_type = None;
+        let mut headers = vec![];
+        let mut cookies = vec![];
+        let mut response_headers = vec![];
+        let mut rejections = false;
+        let mut schemas = vec![];
 
         while !input.is_empty() {
-            // Parse key-value pairs
-            let meta: MetaNameValue = input.parse()?;
+            // Parse key-value pairs, and the `headers(...)`, `cookies(...)`, `response_headers(...)`
+            // and `schemas(...)` lists
+            let meta = match input.parse()? {
+                Meta::NameValue(meta) => meta,
+                Meta::List(list) if list.path.is_ident("headers") => {
+                    headers.extend(list.parse_args_with(parse_params)?);
+                    skip_comma(input)?;
+                    continue;
+                }
+                Meta::List(list) if list.path.is_ident("cookies") => {
+                    cookies.extend(list.parse_args_with(parse_params)?);
+                    skip_comma(input)?;
+                    continue;
+                }
+                Meta::List(list) if list.path.is_ident("schemas") => {
+                    let types =
+                        list.parse_args_with(Punctuated::<syn::Type, Token![,]>::parse_terminated)?;
+                    schemas.extend(types);
+                    skip_comma(input)?;
+                    continue;
+                }
+                Meta::List(list) if list.path.is_ident("response_headers") => {
+                    response_headers.extend(list.parse_args_with(parse_params)?);
+                    skip_comma(input)?;
+                    continue;
+                }
+                meta => return Err(syn::Error::new(meta.path().span(), "Unexpected argument")),
+            };
 
             if meta.path.is_ident("method") {
-                method = match meta.value {
-                    Expr::Lit(s) => match s.lit {
-                        Lit::Str(lit) => Some(lit.value()),
-                        _ => {
-                            return Err(syn::Error::new(
-                                meta.path.span(),
-                                "Expected literal string",
-                            ))
-                        }
-                    },
-                    _ => return Err(syn::Error::new(meta.path.span(), "Expected literal")),
-                };
+                method = Some(parse_str(&meta)?);
             } else if meta.path.is_ident("path") {
-                path = match meta.value {
-                    Expr::Lit(s) => match s.lit {
-                        Lit::Str(lit) => Some(lit.value()),
-                        _ => {
-                            return Err(syn::Error::new(
-                                meta.path.span(),
-                                "Expected literal string",
-                            ))
-                        }
-                    },
-                    _ => return Err(syn::Error::new(meta.path.span(), "Expected literal")),
-                };
+                path_span = Some(meta.value.span());
+                path = Some(parse_str(&meta)?);
+            } else if meta.path.is_ident("path_prefix") {
+                path_prefix = Some((parse_str(&meta)?, meta.value.span()));
             } else if meta.path.is_ident("description") {
-                description = match meta.value {
-                    Expr::Lit(s) => match s.lit {
-                        Lit::Str(lit) => Some(lit.value()),
-                        _ => {
-                            return Err(syn::Error::new(
-                                meta.path.span(),
-                                "Expected literal string",
-                            ))
-                        }
-                    },
-                    _ => return Err(syn::Error::new(meta.path.span(), "Expected literal")),
-                };
+                description = Some(parse_str(&meta)?);
+            } else if meta.path.is_ident("status") {
+                status = Some(parse_status(&meta)?);
+            } else if meta.path.is_ident("summary") {
+                summary = Some(parse_str(&meta)?);
+            } else if meta.path.is_ident("tags") {
+                tags = parse_str_array(&meta)?;
+            } else if meta.path.is_ident("deprecated") {
+                deprecated = parse_bool(&meta)?;
+            } else if meta.path.is_ident("operation_id") {
+                operation_id = Some(parse_str(&meta)?);
+            } else if meta.path.is_ident("content_type") {
+                content_type = Some(parse_str(&meta)?);
+            } else if meta.path.is_ident("response_content_type") {
+                response_content_type = Some(parse_str(&meta)?);
+            } else if meta.path.is_ident("rejections") {
+                rejections = parse_bool(&meta)?;
+            } else if meta.path.is_ident("security") {
+                security = Some(parse_security(&meta)?);
             } else {
                 return Err(syn::Error::new(meta.path.span(), "Unexpected argument"));
             }
 
-            // Consume optional commas between arguments
-            if input.peek(Token![,]) {
-                let _: Token![,] = input.parse()?;
-            }
+            skip_comma(input)?;
         }
 
         // Ensure both `method` and `path` are provided
@@ -78,7 +163,126 @@ impl Parse for MacroArgs {
         Ok(MacroArgs {
             method,
             path,
+            path_span: path_span.unwrap_or_else(proc_macro2::Span::call_site),
+            path_prefix,
             description,
+            status,
+            summary,
+            tags,
+            deprecated,
+            operation_id,
+            security,
+            content_type,
+            response_content_type,
+            headers,
+            cookies,
+            response_headers,
+            rejections,
+            schemas,
         })
     }
 }
+
+/// Consume optional commas between arguments
+fn skip_comma(input: ParseStream) -> syn::Result<()> {
+    if input.peek(Token![,]) {
+        let _: Token![,] = input.parse()?;
+    }
+    Ok(())
+}
+
+fn parse_params(input: ParseStream) -> syn::Result<Vec<ParamArg>> {
+    let params = Punctuated::<ParamArg, Token![,]>::parse_terminated(input)?;
+    Ok(params.into_iter().collect())
+}
+
+fn parse_lit(meta: &MetaNameValue) -> syn::Result<&Lit> {
+    match &meta.value {
+        Expr::Lit(s) => Ok(&s.lit),
+        _ => Err(syn::Error::new(meta.path.span(), "Expected literal")),
+    }
+}
+
+fn parse_str(meta: &MetaNameValue) -> syn::Result<String> {
+    match parse_lit(meta)? {
+        Lit::Str(lit) => Ok(lit.value()),
+        _ => Err(syn::Error::new(meta.path.span(), "Expected literal string")),
+    }
+}
+
+fn parse_bool(meta: &MetaNameValue) -> syn::Result<bool> {
+    match parse_lit(meta)? {
+        Lit::Bool(lit) => Ok(lit.value),
+        _ => Err(syn::Error::new(meta.path.span(), "Expected literal bool")),
+    }
+}
+
+/// `status = 201` or `status = "201"`.
+fn parse_status(meta: &MetaNameValue) -> syn::Result<u16> {
+    let status = match parse_lit(meta)? {
+        Lit::Int(lit) => lit.base10_parse::<u16>().ok(),
+        Lit::Str(lit) => lit.value().parse::<u16>().ok(),
+        _ => None,
+    };
+    status
+        .filter(|status| (100..=599).contains(status))
+        .ok_or_else(|| {
+            syn::Error::new(
+                meta.value.span(),
+                "Expected an HTTP status code between 100 and 599",
+            )
+        })
+}
+
+/// `tags = ["todos", "admin"]`.
+fn parse_str_array(meta: &MetaNameValue) -> syn::Result<Vec<String>> {
+    str_array(&meta.value)
+}
+
+fn str_array(expr: &Expr) -> syn::Result<Vec<String>> {
+    let Expr::Array(array) = expr else {
+        return Err(syn::Error::new(
+            expr.span(),
+            "Expected an array of literal strings",
+        ));
+    };
+    array.elems.iter().map(str_lit).collect()
+}
+
+fn str_lit(expr: &Expr) -> syn::Result<String> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit.value()),
+        _ => Err(syn::Error::new(expr.span(), "Expected literal string")),
+    }
+}
+
+/// `security = ["api_key", ("oauth2" = ["todos:read"]), ("api_key" = [], "oauth2" = []), ()]`:
+/// every element is a requirement, the schemes in a tuple must be all satisfied
+/// and `()` makes the security optional.
+fn parse_security(meta: &MetaNameValue) -> syn::Result<Vec<SecurityRequirement>> {
+    let Expr::Array(array) = &meta.value else {
+        return Err(syn::Error::new(
+            meta.value.span(),
+            "Expected an array of security requirements",
+        ));
+    };
+    array
+        .elems
+        .iter()
+        .map(|elem| match elem {
+            Expr::Paren(paren) => Ok(vec![security_scheme(&paren.expr)?]),
+            Expr::Tuple(tuple) => tuple.elems.iter().map(security_scheme).collect(),
+            _ => Ok(vec![security_scheme(elem)?]),
+        })
+        .collect()
+}
+
+/// `"name"` or `"name" = ["scope"]`.
+fn security_scheme(expr: &Expr) -> syn::Result<(String, Vec<String>)> {
+    match expr {
+        Expr::Assign(assign) => Ok((str_lit(&assign.left)?, str_array(&assign.right)?)),
+        _ => Ok((str_lit(expr)?, vec![])),
+    }
+}
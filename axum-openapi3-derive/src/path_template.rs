@@ -0,0 +1,310 @@
+use syn::spanned::Spanned;
+
+use crate::handler_signature::HandlerArgument;
+
+/// Return the names of the placeholders of the path template, e.g. `id` for `/todos/{id}`.
+pub fn extract_params(input: &str) -> Vec<String> {
+    input
+        .split('/')
+        .filter_map(|segment| {
+            if segment.starts_with('{') && segment.ends_with("}") {
+                Some(
+                    segment
+                        .trim_start_matches('{')
+                        .trim_start_matches('*')
+                        .trim_end_matches("}")
+                        .to_string(),
+                )
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Convert the axum path template to the OpenAPI one: wildcards `{*name}` become `{name}`.
+pub fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix("{*") {
+            Some(name) => format!("{{{name}"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Check the path template follows the axum 0.8 syntax:
+/// it starts with `/`, placeholders are whole `{name}` or `{*name}` segments
+/// with balanced braces and unique names.
+pub fn validate_path(path: &str) -> Result<(), String> {
+    if !path.starts_with('/') {
+        return Err(format!("Path `{path}` must start with `/`"));
+    }
+
+    let mut names = vec![];
+    for segment in path.split('/') {
+        if let Some(name) = segment.strip_prefix(':') {
+            return Err(format!(
+                "Path segment `{segment}` uses the legacy syntax, use `{{{name}}}` instead"
+            ));
+        }
+        if let Some(name) = segment.strip_prefix('*') {
+            return Err(format!(
+                "Path segment `{segment}` uses the legacy syntax, use `{{*{name}}}` instead"
+            ));
+        }
+
+        let opens = segment.matches('{').count();
+        let closes = segment.matches('}').count();
+        if opens == 0 && closes == 0 {
+            continue;
+        }
+        if opens != closes {
+            return Err(format!("Path segment `{segment}` has unbalanced braces"));
+        }
+        let name = segment
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .map(|s| s.trim_start_matches('*'))
+            .filter(|name| !name.is_empty() && !name.contains(['{', '}']))
+            .ok_or_else(|| {
+                format!("Path segment `{segment}` must be a single placeholder like `{{name}}`")
+            })?;
+        if names.contains(&name) {
+            return Err(format!("Path placeholder `{{{name}}}` is declared twice"));
+        }
+        names.push(name);
+    }
+
+    Ok(())
+}
+
+/// Check the `Path` extractors of the handler match the placeholders of the path template.
+pub fn validate_path_extractors(
+    fn_args: &[HandlerArgument],
+    path_param_names: &[String],
+    path_span: proc_macro2::Span,
+) -> Result<(), syn::Error> {
+    let path_args: Vec<_> = fn_args
+        .iter()
+        .filter_map(|arg| match arg {
+            HandlerArgument::Path(ty) => Some(ty),
+            _ => None,
+        })
+        .collect();
+
+    if path_args.is_empty() && !path_param_names.is_empty() {
+        return Err(syn::Error::new(
+            path_span,
+            format!(
+                "Path declares {} placeholder(s) but the handler has no `Path` extractor, \
+                 annotate an aliased extractor with `#[openapi(path = T)]`",
+                path_param_names.len()
+            ),
+        ));
+    }
+
+    for ty in path_args {
+        if path_param_names.is_empty() {
+            return Err(syn::Error::new(
+                ty.span(),
+                "`Path` extractor but the path doesn't declare any placeholder",
+            ));
+        }
+        if is_scalar(ty) && path_param_names.len() > 1 {
+            return Err(syn::Error::new(
+                ty.span(),
+                format!(
+                    "`Path` extractor has a single value but the path declares {} placeholder(s), \
+                     use a tuple or a struct, or declare the placeholders of a nest prefix with `path_prefix`",
+                    path_param_names.len()
+                ),
+            ));
+        }
+        if let syn::Type::Tuple(tuple) = ty {
+            if tuple.elems.len() != path_param_names.len() {
+                return Err(syn::Error::new(
+                    ty.span(),
+                    format!(
+                        "`Path` extractor has {} element(s) but the path declares {} placeholder(s), \
+                         declare the placeholders of a nest prefix with `path_prefix`",
+                        tuple.elems.len(),
+                        path_param_names.len()
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `ty` is deserialized from a single path placeholder, e.g. `u64`, `String` or `Uuid`.
+fn is_scalar(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(reference) => is_scalar(&reference.elem),
+        syn::Type::Path(path) => path.path.segments.last().is_some_and(|segment| {
+            segment.arguments.is_none()
+                && matches!(
+                    segment.ident.to_string().as_str(),
+                    "u8" | "u16"
+                        | "u32"
+                        | "u64"
+                        | "u128"
+                        | "usize"
+                        | "i8"
+                        | "i16"
+                        | "i32"
+                        | "i64"
+                        | "i128"
+                        | "isize"
+                        | "f32"
+                        | "f64"
+                        | "bool"
+                        | "char"
+                        | "str"
+                        | "String"
+                        | "Uuid"
+                )
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler_signature::HandlerArgument;
+    #[test]
+    fn test_extract_params() {
+        assert_eq!(super::extract_params("/foo/{id}/bar"), vec!["id"]);
+        assert_eq!(
+            super::extract_params("/foo/{id}/bar/{baz}"),
+            vec!["id", "baz"]
+        );
+        assert_eq!(
+            super::extract_params("/foo/{id}/bar/{baz}/"),
+            vec!["id", "baz"]
+        );
+        assert_eq!(
+            super::extract_params("/foo/{id}/bar/{baz}/{qux}"),
+            vec!["id", "baz", "qux"]
+        );
+        assert_eq!(super::extract_params("/files/{*path}"), vec!["path"]);
+    }
+
+    #[test]
+    fn test_openapi_path() {
+        assert_eq!(super::openapi_path("/todos/{id}"), "/todos/{id}");
+        assert_eq!(super::openapi_path("/files/{*path}"), "/files/{path}");
+        assert_eq!(
+            super::openapi_path("/a/{id}/files/{*rest}"),
+            "/a/{id}/files/{rest}"
+        );
+        assert_eq!(super::openapi_path("/"), "/");
+    }
+
+    #[test]
+    fn test_validate_path() {
+        let valid = [
+            "/",
+            "/todos",
+            "/todos/{id}",
+            "/a/{a}/b/{b}/",
+            "/files/{*path}",
+        ];
+        for path in valid {
+            assert_eq!(super::validate_path(path), Ok(()), "{path}");
+        }
+
+        let invalid = [
+            "todos",
+            "/todos/:id",
+            "/files/*path",
+            "/todos/{id",
+            "/todos/id}",
+            "/todos/{}",
+            "/todos/{id}-{name}",
+            "/todos/x{id}",
+            "/a/{id}/b/{id}",
+        ];
+        for path in invalid {
+            assert!(super::validate_path(path).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_validate_path_extractors() {
+        let span = proc_macro2::Span::call_site();
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let path = HandlerArgument::Path;
+        let validate = |args: &[HandlerArgument], placeholders: &[&str]| {
+            super::validate_path_extractors(args, &names(placeholders), span)
+                .map_err(|err| err.to_string())
+        };
+
+        assert_eq!(validate(&[], &[]), Ok(()));
+        assert_eq!(validate(&[path(syn::parse_quote!(u64))], &["id"]), Ok(()));
+        assert_eq!(
+            validate(&[path(syn::parse_quote!((String, u64)))], &["org_id", "id"]),
+            Ok(())
+        );
+        // Structs are matched by name, not by count
+        assert_eq!(
+            validate(&[path(syn::parse_quote!(Params))], &["org_id", "id"]),
+            Ok(())
+        );
+        // Other arguments don't extract the placeholders
+        assert_eq!(
+            validate(
+                &[HandlerArgument::Extractor(syn::parse_quote!(Json<u64>))],
+                &[]
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            validate(&[], &["id"]),
+            Err(
+                "Path declares 1 placeholder(s) but the handler has no `Path` extractor, \
+                 annotate an aliased extractor with `#[openapi(path = T)]`"
+                    .into()
+            )
+        );
+        assert_eq!(
+            validate(&[path(syn::parse_quote!(u64))], &[]),
+            Err("`Path` extractor but the path doesn't declare any placeholder".into())
+        );
+        let err = validate(&[path(syn::parse_quote!((u64,)))], &["org_id", "id"]).unwrap_err();
+        assert!(
+            err.starts_with(
+                "`Path` extractor has 1 element(s) but the path declares 2 placeholder(s)"
+            ),
+            "{err}"
+        );
+        for scalar in [
+            syn::parse_quote!(u64),
+            syn::parse_quote!(String),
+            syn::parse_quote!(uuid::Uuid),
+        ] {
+            let err = validate(&[path(scalar)], &["org", "id"]).unwrap_err();
+            assert!(
+                err.starts_with(
+                    "`Path` extractor has a single value but the path declares 2 placeholder(s)"
+                ),
+                "{err}"
+            );
+        }
+        let err = validate(&[path(syn::parse_quote!((String, u64)))], &["id"]).unwrap_err();
+        assert!(
+            err.starts_with(
+                "`Path` extractor has 2 element(s) but the path declares 1 placeholder(s)"
+            ),
+            "{err}"
+        );
+    }
+}
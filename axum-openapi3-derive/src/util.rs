@@ -1,34 +1,153 @@
-use syn::TypePath;
+use proc_macro2::TokenStream;
+use quote::quote;
 
-pub fn recursive_type(type_path: &TypePath, v: &mut Vec<String>) {
-    v.push(type_path.path.segments[0].ident.to_string());
-    if let syn::PathArguments::AngleBracketed(ref args) = type_path.path.segments[0].arguments {
-        for arg in args.args.iter() {
-            match arg {
-                syn::GenericArgument::Type(ty) => {
-                    match ty {
-                        syn::Type::Path(path) => {
-                            recursive_type(path, v);
-                        }
-                        syn::Type::Reference(r) => {
-                            match r.elem.as_ref() {
-                                syn::Type::Path(path) => {
-                                    recursive_type(path, v);
-                                }
-                                _ => {
-                                    // eprintln!("Unknown type: {:?}", a);
-                                }
-                            }
-                        }
-                        _ => {
-                            // println!("Unknown type: {:?}", a);
-                        }
-                    }
-                }
-                _ => {
-                    // println!("Unknown type: {:?}", a);
-                }
+/// Return the name of the outermost type and its first generic argument.
+/// For instance, `Json` and `Vec<Todo>` for `Json<Vec<Todo>>` or `axum::Json<Vec<Todo>>`.
+pub fn extractor_type(ty: &syn::Type) -> Option<(String, &syn::Type)> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        syn::GenericArgument::Type(inner) => Some((segment.ident.to_string(), inner)),
+        _ => None,
+    })
+}
+
+/// The generic type arguments of the last segment of `ty`, e.g. `String` and `u64` for `HashMap<String, u64>`.
+fn generic_types(ty: &syn::Type) -> Vec<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return vec![];
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return vec![];
+    };
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return vec![];
+    };
+    args.args
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether utoipa cannot provide the schema of `ty`, so it's built by [`schema_token`]:
+/// tuples and arrays, also nested in other types (e.g. `Option<(u64, String)>` or `Vec<[u8; 32]>`).
+pub fn is_composite(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Tuple(tuple) => !tuple.elems.is_empty(),
+        syn::Type::Array(_) | syn::Type::Slice(_) => true,
+        syn::Type::Paren(paren) => is_composite(&paren.elem),
+        syn::Type::Group(group) => is_composite(&group.elem),
+        syn::Type::Reference(reference) => is_composite(&reference.elem),
+        syn::Type::Path(_) => generic_types(ty).into_iter().any(is_composite),
+        _ => false,
+    }
+}
+
+/// The types which can wrap a composite type: how their schema is built from the inner one.
+enum Wrapper {
+    Option,
+    Sequence,
+    Pointer,
+}
+
+impl Wrapper {
+    fn from_type(ty: &syn::Type) -> Option<(Self, &syn::Type)> {
+        let syn::Type::Path(type_path) = ty else {
+            return None;
+        };
+        let wrapper = match type_path.path.segments.last()?.ident.to_string().as_str() {
+            "Option" => Wrapper::Option,
+            "Vec" | "VecDeque" | "LinkedList" => Wrapper::Sequence,
+            "Box" | "Rc" | "Arc" => Wrapper::Pointer,
+            _ => return None,
+        };
+        match generic_types(ty).as_slice() {
+            [inner] => Some((wrapper, inner)),
+            _ => None,
+        }
+    }
+}
+
+/// Expression evaluating to the `RefOr<Schema>` of `ty`.
+/// Tuples and arrays, which don't implement `PartialSchema`, are built element by element,
+/// also when wrapped in `Option`, `Vec`, `VecDeque`, `LinkedList`, `Box`, `Rc`, `Arc`, slices or references.
+/// They are rejected in other types.
+pub fn schema_token(ty: &syn::Type) -> TokenStream {
+    match ty {
+        syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let items = tuple.elems.iter().map(schema_token);
+            quote! {
+                axum_openapi3::__private::tuple_schema(vec![#(#items),*])
+            }
+        }
+        syn::Type::Array(array) => {
+            let item = schema_token(&array.elem);
+            let len = &array.len;
+            quote! {
+                axum_openapi3::__private::array_schema(#item, #len)
+            }
+        }
+        syn::Type::Slice(slice) => {
+            let item = schema_token(&slice.elem);
+            quote! { axum_openapi3::__private::sequence_schema(#item) }
+        }
+        syn::Type::Paren(paren) => schema_token(&paren.elem),
+        syn::Type::Group(group) => schema_token(&group.elem),
+        syn::Type::Reference(reference) if is_composite(&reference.elem) => {
+            schema_token(&reference.elem)
+        }
+        syn::Type::Path(_) if is_composite(ty) => match Wrapper::from_type(ty) {
+            Some((Wrapper::Option, inner)) => {
+                let inner = schema_token(inner);
+                quote! { axum_openapi3::__private::nullable_schema(#inner) }
+            }
+            Some((Wrapper::Sequence, inner)) => {
+                let inner = schema_token(inner);
+                quote! { axum_openapi3::__private::sequence_schema(#inner) }
             }
+            Some((Wrapper::Pointer, inner)) => schema_token(inner),
+            None => syn::Error::new_spanned(
+                ty,
+                "Tuples and arrays are supported only in `Option`, `Vec`, `VecDeque`, `LinkedList`, \
+                 `Box`, `Rc`, `Arc`, slices and references: use a type implementing `ToSchema` instead",
+            )
+            .to_compile_error(),
+        },
+        _ => quote! {
+            < #ty as axum_openapi3::utoipa::PartialSchema >::schema()
+        },
+    }
+}
+
+/// Statements pushing the schemas referenced by `ty` into `schemas`.
+pub fn schemas_token(ty: &syn::Type) -> TokenStream {
+    match ty {
+        syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let items = tuple.elems.iter().map(schemas_token);
+            quote! { #(#items)* }
+        }
+        syn::Type::Array(array) => schemas_token(&array.elem),
+        syn::Type::Slice(slice) => schemas_token(&slice.elem),
+        syn::Type::Paren(paren) => schemas_token(&paren.elem),
+        syn::Type::Group(group) => schemas_token(&group.elem),
+        syn::Type::Reference(reference) if is_composite(&reference.elem) => {
+            schemas_token(&reference.elem)
         }
+        // Unsupported wrappers are reported by `schema_token`
+        syn::Type::Path(_) if is_composite(ty) => match Wrapper::from_type(ty) {
+            Some((_, inner)) => schemas_token(inner),
+            None => quote! {},
+        },
+        _ => quote! {
+            < #ty as axum_openapi3::utoipa::ToSchema >::schemas(&mut schemas);
+        },
     }
 }
@@ -32,7 +32,7 @@ async fn insert_todo(_: Json<Todo>) -> Json<Todo> {
 }
 #[endpoint(
     method = "PATCH",
-    path = "/todos/:id/complete",
+    path = "/todos/{id}/complete",
     description = "Mark a todo as completed"
 )]
 async fn mark_todo_as_complete(_: Path<u64>, _: Json<u64>) -> Json<Todo> {
@@ -62,7 +62,8 @@ async fn openapi() -> impl IntoResponse {
         OpenApiBuilder::new().info(InfoBuilder::new().title("My Webserver").version("0.1.0"))
     });
 
-    Json(openapi)
+    // The spec is served as JSON, serialized only once
+    openapi
 }
 
 #[tokio::main]
@@ -52,7 +52,7 @@ async fn mark_todo_as_complete(_: Json<u64>) -> Json<Todo> {
 async fn filter(_: Query<TodoFilter>) -> Json<Vec<Todo>> {
     unreachable!("")
 }
-#[endpoint(method = "GET", path = "/todos/:id", description = "Get todo by id")]
+#[endpoint(method = "GET", path = "/todos/{id}", description = "Get todo by id")]
 async fn get_todo(Path(_): Path<u64>) -> Json<Todo> {
     unreachable!("")
 }
@@ -74,7 +74,8 @@ async fn openapi() -> impl IntoResponse {
         OpenApiBuilder::new().info(InfoBuilder::new().title("My Webserver").version("0.1.0"))
     });
 
-    Json(openapi)
+    // The spec is served as JSON, serialized only once
+    openapi
 }
 
 #[tokio::main]
@@ -0,0 +1,477 @@
+//! Helpers used by the code generated by the `endpoint` macro.
+//! Don't use directly.
+//!
+//! The macro cannot know if a type implements a trait, so it uses autoref specialization:
+//! `(&&Wrap::<T>::new()).method()` resolves to the `*ViaTrait` implementation when `T`
+//! implements the trait, to the `*Fallback` one otherwise.
+//! Both traits must be in scope.
+
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use axum::http::StatusCode;
+use utoipa::{
+    openapi::{
+        encoding::Encoding,
+        path::{Operation, Parameter, ParameterBuilder, ParameterIn, ParameterStyle},
+        schema::{
+            AdditionalProperties, AllOfBuilder, ArrayBuilder, ArrayItems, KnownFormat,
+            ObjectBuilder, OneOfBuilder, Schema, SchemaFormat, Type,
+        },
+        Components, ContentBuilder, Header, Ref, RefOr, Required, Response, ResponseBuilder,
+    },
+    IntoParams, IntoResponses, PartialSchema, ToSchema,
+};
+
+use crate::{
+    operation::{add_schemas, set_request_body},
+    ErrorResponses, MultipartForm, OperationArg, RejectionResponses, ResponseHeaders,
+};
+
+pub struct Wrap<T>(PhantomData<T>);
+
+impl<T> Wrap<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+pub trait ErrorResponsesViaTrait {
+    fn error_responses(&self) -> BTreeMap<String, RefOr<Response>>;
+    fn error_schemas(&self, schemas: &mut Vec<(String, RefOr<Schema>)>);
+}
+
+impl<T: ErrorResponses> ErrorResponsesViaTrait for &Wrap<T> {
+    fn error_responses(&self) -> BTreeMap<String, RefOr<Response>> {
+        T::responses()
+    }
+    fn error_schemas(&self, schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        T::schemas(schemas)
+    }
+}
+
+pub trait ErrorResponsesFallback {
+    fn error_responses(&self) -> BTreeMap<String, RefOr<Response>>;
+    fn error_schemas(&self, schemas: &mut Vec<(String, RefOr<Schema>)>);
+}
+
+impl<T> ErrorResponsesFallback for Wrap<T> {
+    fn error_responses(&self) -> BTreeMap<String, RefOr<Response>> {
+        BTreeMap::new()
+    }
+    fn error_schemas(&self, _: &mut Vec<(String, RefOr<Schema>)>) {}
+}
+
+/// Success and error types implementing `utoipa::IntoResponses`, e.g. with `#[derive(IntoResponses)]`.
+pub trait IntoResponsesViaTrait {
+    fn responses(&self) -> Option<BTreeMap<String, RefOr<Response>>>;
+}
+
+impl<T: IntoResponses> IntoResponsesViaTrait for &Wrap<T> {
+    fn responses(&self) -> Option<BTreeMap<String, RefOr<Response>>> {
+        Some(T::responses())
+    }
+}
+
+pub trait IntoResponsesFallback {
+    fn responses(&self) -> Option<BTreeMap<String, RefOr<Response>>>;
+}
+
+impl<T> IntoResponsesFallback for Wrap<T> {
+    fn responses(&self) -> Option<BTreeMap<String, RefOr<Response>>> {
+        None
+    }
+}
+
+pub trait ResponseHeadersViaTrait {
+    fn response_headers(&self) -> BTreeMap<String, Header>;
+}
+
+impl<T: ResponseHeaders> ResponseHeadersViaTrait for &Wrap<T> {
+    fn response_headers(&self) -> BTreeMap<String, Header> {
+        T::headers()
+    }
+}
+
+pub trait ResponseHeadersFallback {
+    fn response_headers(&self) -> BTreeMap<String, Header>;
+}
+
+impl<T> ResponseHeadersFallback for Wrap<T> {
+    fn response_headers(&self) -> BTreeMap<String, Header> {
+        BTreeMap::new()
+    }
+}
+
+pub trait RejectionResponsesViaTrait {
+    fn rejections(&self) -> Vec<(StatusCode, &'static str)>;
+}
+
+impl<T: RejectionResponses> RejectionResponsesViaTrait for &Wrap<T> {
+    fn rejections(&self) -> Vec<(StatusCode, &'static str)> {
+        T::rejections()
+    }
+}
+
+pub trait RejectionResponsesFallback {
+    fn rejections(&self) -> Vec<(StatusCode, &'static str)>;
+}
+
+impl<T> RejectionResponsesFallback for Wrap<T> {
+    fn rejections(&self) -> Vec<(StatusCode, &'static str)> {
+        vec![]
+    }
+}
+
+/// The rejections of `#[openapi(multipart = T)]` arguments, whose type is not known to the macro.
+pub struct MultipartRejections;
+
+impl RejectionResponses for MultipartRejections {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![
+            (
+                StatusCode::BAD_REQUEST,
+                "The request body is not a valid multipart form",
+            ),
+            (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "The request body is too large",
+            ),
+            (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "The request body is not `multipart/form-data`",
+            ),
+        ]
+    }
+}
+
+/// Name of the schema shared by the rejection responses.
+const REJECTION_SCHEMA: &str = "ExtractorRejection";
+
+/// Add a `text/plain` response for every rejection, unless the status is already documented.
+/// The responses reference the shared rejection schema, which is added to `components`.
+pub fn add_rejection_responses(
+    operation: &mut Operation,
+    components: &mut Components,
+    rejections: Vec<(StatusCode, &'static str)>,
+) {
+    if rejections.is_empty() {
+        return;
+    }
+    components
+        .schemas
+        .entry(REJECTION_SCHEMA.to_string())
+        .or_insert_with(|| {
+            ObjectBuilder::new()
+                .schema_type(Type::String)
+                .description(Some("The reason why an extractor rejected the request"))
+                .into()
+        });
+    for (status, description) in rejections {
+        operation
+            .responses
+            .responses
+            .entry(status.as_u16().to_string())
+            .or_insert_with(|| {
+                ResponseBuilder::new()
+                    .description(description)
+                    .content(
+                        "text/plain",
+                        ContentBuilder::new()
+                            .schema(Some(Ref::from_schema_name(REJECTION_SCHEMA)))
+                            .build(),
+                    )
+                    .into()
+            });
+    }
+}
+
+pub trait OperationArgViaTrait {
+    fn document(&self, operation: &mut Operation, components: &mut Components);
+}
+
+impl<T: OperationArg> OperationArgViaTrait for &Wrap<T> {
+    fn document(&self, operation: &mut Operation, components: &mut Components) {
+        T::document(operation, components)
+    }
+}
+
+pub trait OperationArgFallback {
+    fn document(&self, operation: &mut Operation, components: &mut Components);
+}
+
+impl<T> OperationArgFallback for Wrap<T> {
+    fn document(&self, _: &mut Operation, _: &mut Components) {}
+}
+
+/// `Path<T>` where `T` implements `IntoParams`: the parameters are named after the fields.
+/// The schemas referenced by the fields are declared with `schemas(...)`.
+pub trait PathParamsViaIntoParams {
+    fn path_params(
+        &self,
+        names: &[&str],
+        schemas: &mut Vec<(String, RefOr<Schema>)>,
+    ) -> Vec<Parameter>;
+}
+
+impl<T: IntoParams> PathParamsViaIntoParams for &Wrap<T> {
+    fn path_params(&self, _: &[&str], _: &mut Vec<(String, RefOr<Schema>)>) -> Vec<Parameter> {
+        T::into_params(|| Some(ParameterIn::Path))
+    }
+}
+
+/// `Path<T>` where `T` implements `ToSchema`: a parameter per placeholder of the path template.
+/// With several placeholders, `T` is a struct or a map: the schema of each parameter is
+/// the one of the matching property, or of the map values.
+/// Panics if a placeholder has no matching property.
+pub trait PathParamsViaSchema {
+    fn path_params(
+        &self,
+        names: &[&str],
+        schemas: &mut Vec<(String, RefOr<Schema>)>,
+    ) -> Vec<Parameter>;
+}
+
+impl<T: ToSchema> PathParamsViaSchema for Wrap<T> {
+    fn path_params(
+        &self,
+        names: &[&str],
+        schemas: &mut Vec<(String, RefOr<Schema>)>,
+    ) -> Vec<Parameter> {
+        T::schemas(schemas);
+        let schema = T::schema();
+        if let [name] = names {
+            return vec![path_param(name, schema)];
+        }
+        names
+            .iter()
+            .map(|name| {
+                let property = property_schema(&schema, name).unwrap_or_else(|| {
+                    panic!(
+                        "`Path<{}>` has no property for the placeholder `{{{name}}}`",
+                        std::any::type_name::<T>()
+                    )
+                });
+                path_param(name, property)
+            })
+            .collect()
+    }
+}
+
+/// The schema of the property `name` of an object schema, or of the values of a map schema.
+fn property_schema(schema: &RefOr<Schema>, name: &str) -> Option<RefOr<Schema>> {
+    let RefOr::T(Schema::Object(object)) = schema else {
+        return None;
+    };
+    match (object.properties.get(name), &object.additional_properties) {
+        (Some(property), _) => Some(property.clone()),
+        (None, Some(additional)) => match additional.as_ref() {
+            AdditionalProperties::RefOr(values) => Some(values.clone()),
+            AdditionalProperties::FreeForm(true) => Some(String::schema()),
+            AdditionalProperties::FreeForm(false) => None,
+        },
+        (None, None) => None,
+    }
+}
+
+pub trait MultipartFormViaTrait {
+    fn multipart_encodings(&self) -> BTreeMap<String, Encoding>;
+}
+
+impl<T: MultipartForm> MultipartFormViaTrait for &Wrap<T> {
+    fn multipart_encodings(&self) -> BTreeMap<String, Encoding> {
+        T::encodings()
+    }
+}
+
+pub trait MultipartFormFallback {
+    fn multipart_encodings(&self) -> BTreeMap<String, Encoding>;
+}
+
+impl<T> MultipartFormFallback for Wrap<T> {
+    fn multipart_encodings(&self) -> BTreeMap<String, Encoding> {
+        BTreeMap::new()
+    }
+}
+
+/// Document `T` as `application/x-www-form-urlencoded` request body, for `#[openapi(form)]`.
+pub fn form_body<T: ToSchema>(operation: &mut Operation, components: &mut Components) {
+    set_request_body(
+        operation,
+        "application/x-www-form-urlencoded",
+        Some(T::schema()),
+        BTreeMap::new(),
+    );
+    add_schemas::<T>(components);
+}
+
+/// Document the properties of `T` as query parameters, for a `Form` of a `GET` or `HEAD` handler.
+/// A schema without properties, e.g. a map, is a single exploded query parameter named after `T`.
+pub fn form_query<T: ToSchema>(operation: &mut Operation, components: &mut Components) {
+    let parameters = operation.parameters.get_or_insert_with(Vec::new);
+    match T::schema() {
+        RefOr::T(Schema::Object(object)) if !object.properties.is_empty() => {
+            for (name, schema) in object.properties {
+                let required = object.required.contains(&name);
+                parameters.push(param(&name, ParameterIn::Query, required, schema));
+            }
+        }
+        schema => parameters.push(
+            ParameterBuilder::from(param(&T::name(), ParameterIn::Query, true, schema))
+                .style(Some(ParameterStyle::Form))
+                .explode(Some(true))
+                .build(),
+        ),
+    }
+    add_schemas::<T>(components);
+}
+
+/// Document `T` as `multipart/form-data` request body, for `#[openapi(multipart = T)]`.
+pub fn multipart_body<T: ToSchema>(
+    operation: &mut Operation,
+    components: &mut Components,
+    encodings: BTreeMap<String, Encoding>,
+) {
+    set_request_body(
+        operation,
+        "multipart/form-data",
+        Some(T::schema()),
+        encodings,
+    );
+    add_schemas::<T>(components);
+}
+
+pub fn path_param(name: &str, schema: RefOr<Schema>) -> Parameter {
+    param(name, ParameterIn::Path, true, schema)
+}
+
+pub fn param(
+    name: &str,
+    parameter_in: ParameterIn,
+    required: bool,
+    schema: RefOr<Schema>,
+) -> Parameter {
+    let required = if required {
+        Required::True
+    } else {
+        Required::False
+    };
+    ParameterBuilder::new()
+        .parameter_in(parameter_in)
+        .name(name)
+        .required(required)
+        .schema(Some(schema))
+        .build()
+}
+
+/// Documents an `Option<T>` argument: what `T` adds to the operation becomes not required.
+///
+/// Take it with [`OptionalArg::before`], document `T`, then call [`OptionalArg::after`].
+pub struct OptionalArg {
+    request_body: bool,
+    parameters: usize,
+}
+
+impl OptionalArg {
+    pub fn before(operation: &Operation) -> Self {
+        Self {
+            request_body: operation.request_body.is_some(),
+            parameters: operation.parameters.as_ref().map_or(0, Vec::len),
+        }
+    }
+
+    /// Mark the request body and the parameters added since [`OptionalArg::before`] as not required.
+    pub fn after(self, operation: &mut Operation) {
+        if let (false, Some(request_body)) = (self.request_body, &mut operation.request_body) {
+            request_body.required = Some(Required::False);
+        }
+        if let Some(parameters) = &mut operation.parameters {
+            for parameter in parameters.iter_mut().skip(self.parameters) {
+                parameter.required = Required::False;
+            }
+        }
+    }
+}
+
+/// Add `headers` to the success (`2XX`) responses of `operation`, without overriding the ones already documented.
+pub fn add_response_headers(operation: &mut Operation, headers: BTreeMap<String, Header>) {
+    let success = operation
+        .responses
+        .responses
+        .iter_mut()
+        .filter(|(status, _)| status.starts_with('2'));
+    for (_, response) in success {
+        if let RefOr::T(response) = response {
+            for (name, header) in &headers {
+                response
+                    .headers
+                    .entry(name.clone())
+                    .or_insert_with(|| header.clone());
+            }
+        }
+    }
+}
+
+/// Schema of a binary body: a string with `binary` format.
+pub fn binary_schema() -> RefOr<Schema> {
+    ObjectBuilder::new()
+        .schema_type(Type::String)
+        .format(Some(SchemaFormat::KnownFormat(KnownFormat::Binary)))
+        .into()
+}
+
+/// Set the content type of the request body, for the `content_type` argument.
+/// Without a documented request body, a required one without schema is added.
+pub fn set_request_content_type(operation: &mut Operation, content_type: &str) {
+    let Some(request_body) = &mut operation.request_body else {
+        set_request_body(operation, content_type, None, BTreeMap::new());
+        return;
+    };
+    let content = std::mem::take(&mut request_body.content)
+        .into_values()
+        .next()
+        .unwrap_or_default();
+    request_body
+        .content
+        .insert(content_type.to_string(), content);
+}
+
+/// Schema of a tuple: an array with a fixed item for each position.
+pub fn tuple_schema(items: Vec<RefOr<Schema>>) -> RefOr<Schema> {
+    let len = items.len();
+    ArrayBuilder::new()
+        .items(ArrayItems::False)
+        .prefix_items(items.into_iter().map(inline_schema))
+        .min_items(Some(len))
+        .max_items(Some(len))
+        .into()
+}
+
+/// Schema of a fixed size array.
+pub fn array_schema(item: RefOr<Schema>, len: usize) -> RefOr<Schema> {
+    ArrayBuilder::new()
+        .items(item)
+        .min_items(Some(len))
+        .max_items(Some(len))
+        .into()
+}
+
+/// Schema of a sequence of `item`, e.g. `Vec<(u64, String)>`.
+pub fn sequence_schema(item: RefOr<Schema>) -> RefOr<Schema> {
+    ArrayBuilder::new().items(item).into()
+}
+
+/// Schema of an `Option` of `schema`, as utoipa documents `Option<T>`.
+pub fn nullable_schema(schema: RefOr<Schema>) -> RefOr<Schema> {
+    OneOfBuilder::new()
+        .item(ObjectBuilder::new().schema_type(Type::Null))
+        .item(schema)
+        .into()
+}
+
+fn inline_schema(schema: RefOr<Schema>) -> Schema {
+    match schema {
+        RefOr::T(schema) => schema,
+        RefOr::Ref(reference) => AllOfBuilder::new().item(reference).into(),
+    }
+}
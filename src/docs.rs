@@ -0,0 +1,274 @@
+use std::sync::{Arc, OnceLock};
+
+#[cfg(any(feature = "swagger-ui", feature = "redoc", feature = "scalar"))]
+use axum::http::header;
+use axum::{http::HeaderMap, routing::get, Router};
+use utoipa::openapi::OpenApi;
+
+use crate::{OpenApiSnapshot, SpecFormat};
+
+/// Configuration of the routes mounted by `OpenApiRouter::docs`:
+/// the openapi spec and, with the respective features, the documentation UIs.
+///
+/// The paths are absolute: mount the documentation on the root router, not on a nested one.
+#[derive(Debug, Clone)]
+pub struct DocsConfig {
+    spec_path: String,
+    #[cfg(feature = "swagger-ui")]
+    swagger_ui_path: Option<String>,
+    #[cfg(feature = "redoc")]
+    redoc_path: Option<String>,
+    #[cfg(feature = "scalar")]
+    scalar_path: Option<String>,
+}
+
+impl DocsConfig {
+    /// Serve the openapi spec at `spec_path`, e.g. `/openapi.json`.
+    /// The spec is served as JSON or, with the `yaml` feature, as YAML. See [`SpecFormat`](crate::SpecFormat).
+    pub fn new(spec_path: impl Into<String>) -> Self {
+        Self {
+            spec_path: spec_path.into(),
+            #[cfg(feature = "swagger-ui")]
+            swagger_ui_path: None,
+            #[cfg(feature = "redoc")]
+            redoc_path: None,
+            #[cfg(feature = "scalar")]
+            scalar_path: None,
+        }
+    }
+
+    /// Serve Swagger UI at `path`, e.g. `/swagger-ui`.
+    #[cfg(feature = "swagger-ui")]
+    pub fn swagger_ui(mut self, path: impl Into<String>) -> Self {
+        self.swagger_ui_path = Some(path.into());
+        self
+    }
+
+    /// Serve Redoc at `path`, e.g. `/redoc`.
+    #[cfg(feature = "redoc")]
+    pub fn redoc(mut self, path: impl Into<String>) -> Self {
+        self.redoc_path = Some(path.into());
+        self
+    }
+
+    /// Serve Scalar at `path`, e.g. `/scalar`.
+    #[cfg(feature = "scalar")]
+    pub fn scalar(mut self, path: impl Into<String>) -> Self {
+        self.scalar_path = Some(path.into());
+        self
+    }
+}
+
+/// The spec served by `OpenApiRouter::docs`.
+///
+/// It's built when the router is converted into an axum router, from the endpoints added
+/// before and after `docs`, including the ones of the routers it's nested or merged into.
+#[derive(Debug)]
+pub(crate) struct DocsSpec {
+    /// The spec built by the builder given to `docs`, without the endpoints.
+    base: OpenApi,
+    snapshot: OnceLock<OpenApiSnapshot>,
+}
+
+impl DocsSpec {
+    pub(crate) fn new(base: OpenApi) -> Arc<Self> {
+        Arc::new(Self {
+            base,
+            snapshot: OnceLock::new(),
+        })
+    }
+
+    /// Build the served spec from `openapi`, the spec of the endpoints of the router.
+    pub(crate) fn finish(&self, openapi: &OpenApi) {
+        self.snapshot.get_or_init(|| {
+            let openapi = self.base.clone().merge_from(openapi.clone());
+            crate::refs::check_refs(&openapi);
+            OpenApiSnapshot::new(openapi)
+        });
+    }
+}
+
+/// Add the routes described by `config` to `router`, serving `spec`.
+pub(crate) fn mount<S: Clone + Send + Sync + 'static>(
+    router: Router<S>,
+    spec: Arc<DocsSpec>,
+    config: &DocsConfig,
+) -> Router<S> {
+    let router = router.route(
+        &config.spec_path,
+        get(move |format: SpecFormat, headers: HeaderMap| async move {
+            spec.snapshot
+                .get()
+                .expect("the spec is built when the `OpenApiRouter` is converted into a `Router`")
+                .response(format, &headers)
+        }),
+    );
+
+    #[cfg(feature = "swagger-ui")]
+    let router = match &config.swagger_ui_path {
+        Some(path) => swagger_ui::mount(router, path, &config.spec_path),
+        None => router,
+    };
+    #[cfg(feature = "redoc")]
+    let router = match &config.redoc_path {
+        Some(path) => redoc::mount(router, path, &config.spec_path),
+        None => router,
+    };
+    #[cfg(feature = "scalar")]
+    let router = match &config.scalar_path {
+        Some(path) => scalar::mount(router, path, &config.spec_path),
+        None => router,
+    };
+
+    router
+}
+
+#[cfg(any(feature = "swagger-ui", feature = "redoc", feature = "scalar"))]
+fn html_route<S: Clone + Send + Sync + 'static>(
+    router: Router<S>,
+    path: &str,
+    html: String,
+) -> Router<S> {
+    router.route(path, get(move || async move { axum::response::Html(html) }))
+}
+
+#[cfg(any(feature = "swagger-ui", feature = "redoc", feature = "scalar"))]
+fn asset_route<S: Clone + Send + Sync + 'static>(
+    router: Router<S>,
+    path: String,
+    content_type: &'static str,
+    content: &'static str,
+) -> Router<S> {
+    router.route(
+        &path,
+        get(move || async move { ([(header::CONTENT_TYPE, content_type)], content) }),
+    )
+}
+
+#[cfg(feature = "swagger-ui")]
+mod swagger_ui {
+    use axum::Router;
+
+    const BUNDLE_JS: &str = include_str!("../assets/swagger-ui/swagger-ui-bundle.js");
+    const CSS: &str = include_str!("../assets/swagger-ui/swagger-ui.css");
+
+    pub fn mount<S: Clone + Send + Sync + 'static>(
+        router: Router<S>,
+        path: &str,
+        spec_path: &str,
+    ) -> Router<S> {
+        let base = path.trim_end_matches('/');
+        let html = format!(
+            r##"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Swagger UI</title>
+    <link rel="stylesheet" href="{base}/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="{base}/swagger-ui-bundle.js"></script>
+    <script>
+      window.onload = () => {{
+        window.ui = SwaggerUIBundle({{ url: "{spec_path}", dom_id: "#swagger-ui" }});
+      }};
+    </script>
+  </body>
+</html>
+"##
+        );
+
+        let router = super::html_route(router, path, html);
+        let router = super::asset_route(
+            router,
+            format!("{base}/swagger-ui-bundle.js"),
+            "text/javascript",
+            BUNDLE_JS,
+        );
+        super::asset_route(router, format!("{base}/swagger-ui.css"), "text/css", CSS)
+    }
+}
+
+#[cfg(feature = "redoc")]
+mod redoc {
+    use axum::Router;
+
+    const STANDALONE_JS: &str = include_str!("../assets/redoc/redoc.standalone.js");
+
+    pub fn mount<S: Clone + Send + Sync + 'static>(
+        router: Router<S>,
+        path: &str,
+        spec_path: &str,
+    ) -> Router<S> {
+        let base = path.trim_end_matches('/');
+        let html = format!(
+            r##"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Redoc</title>
+    <style>
+      body {{ margin: 0; padding: 0; }}
+    </style>
+  </head>
+  <body>
+    <redoc spec-url="{spec_path}"></redoc>
+    <script src="{base}/redoc.standalone.js"></script>
+  </body>
+</html>
+"##
+        );
+
+        let router = super::html_route(router, path, html);
+        super::asset_route(
+            router,
+            format!("{base}/redoc.standalone.js"),
+            "text/javascript",
+            STANDALONE_JS,
+        )
+    }
+}
+
+#[cfg(feature = "scalar")]
+mod scalar {
+    use axum::Router;
+
+    const STANDALONE_JS: &str = include_str!("../assets/scalar/scalar.min.js");
+
+    pub fn mount<S: Clone + Send + Sync + 'static>(
+        router: Router<S>,
+        path: &str,
+        spec_path: &str,
+    ) -> Router<S> {
+        let base = path.trim_end_matches('/');
+        let html = format!(
+            r##"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Scalar</title>
+    <style>
+      body {{ margin: 0; }}
+    </style>
+  </head>
+  <body>
+    <script id="api-reference" data-url="{spec_path}"></script>
+    <script src="{base}/scalar.min.js"></script>
+  </body>
+</html>
+"##
+        );
+
+        let router = super::html_route(router, path, html);
+        super::asset_route(
+            router,
+            format!("{base}/scalar.min.js"),
+            "text/javascript",
+            STANDALONE_JS,
+        )
+    }
+}
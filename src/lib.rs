@@ -1,14 +1,12 @@
 #![doc = include_str!("../README.md")]
 
-use std::{
-    ops::Deref,
-    sync::{Mutex, MutexGuard},
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
 };
 
+use arc_swap::ArcSwapOption;
 use axum::Router;
-use once_cell::sync::Lazy;
-use serde::Serialize;
-use utoipa::openapi::Paths;
 
 #[cfg(feature = "derive")]
 extern crate axum_openapi3_derive;
@@ -19,87 +17,134 @@ pub use axum_openapi3_derive::endpoint;
 /// Re-export utoipa. Used internally to generate the openapi spec from rust structs.
 pub use utoipa;
 
+mod docs;
+mod operation;
+mod problem;
+mod refs;
+mod responses;
+mod router;
+mod snapshot;
+mod spec;
+
+#[doc(hidden)]
+pub mod __private;
+
+pub use docs::DocsConfig;
+pub use operation::{MultipartForm, OperationArg, RejectionResponses};
+pub use problem::{ProblemDetails, PROBLEM_JSON};
+pub use refs::unresolved_refs;
+pub use responses::{ErrorResponses, ResponseHeaders};
+pub use router::OpenApiRouter;
+pub use snapshot::OpenApiSnapshot;
+pub use spec::{spec_handler, SpecFormat};
+
 /// Mutex to store the endpoints.
+/// Every endpoint is stored as a partial openapi spec, containing its path and the schemas it references.
 /// Don't use directly, use the `endpoint` macro instead.
-pub static ENDPOINTS: std::sync::Mutex<Vec<utoipa::openapi::Paths>> = std::sync::Mutex::new(vec![]);
+pub static ENDPOINTS: std::sync::Mutex<Vec<utoipa::openapi::OpenApi>> =
+    std::sync::Mutex::new(vec![]);
+
+/// An endpoint generated by the `endpoint` macro.
+/// It contains the axum route and the partial openapi spec describing it.
+pub struct Endpoint<S> {
+    /// The path used to register the route in axum.
+    pub path: &'static str,
+    /// The axum handler.
+    pub method_router: axum::routing::MethodRouter<S, std::convert::Infallible>,
+    /// The partial openapi spec: the path of the endpoint and the schemas it references.
+    pub openapi: utoipa::openapi::OpenApi,
+}
 
 /// Add `add` method to `Router` to add routes also to the openapi spec.
 pub trait AddRoute<S> {
-    fn add(
-        self,
-        r: (
-            &str,
-            axum::routing::MethodRouter<S, std::convert::Infallible>,
-        ),
-    ) -> Self;
+    fn add(self, endpoint: Endpoint<S>) -> Self;
 }
 
+/// Routes added to a plain `Router` are documented in the global openapi spec,
+/// returned by [`build_openapi`].
 impl<S: std::clone::Clone + std::marker::Send + std::marker::Sync + 'static> AddRoute<S>
     for Router<S>
 {
-    fn add(
-        self,
-        r: (
-            &str,
-            axum::routing::MethodRouter<S, std::convert::Infallible>,
-        ),
-    ) -> Self {
-        self.route(r.0, r.1)
+    fn add(self, endpoint: Endpoint<S>) -> Self {
+        let mut endpoints = ENDPOINTS.lock().unwrap();
+        endpoints.push(endpoint.openapi);
+        REGISTERED.fetch_add(1, Ordering::Release);
+        drop(endpoints);
+        self.route(endpoint.path, endpoint.method_router)
     }
 }
 
-static OPENAPI_BUILT: Lazy<Mutex<Option<utoipa::openapi::OpenApi>>> =
-    Lazy::new(|| Mutex::new(None));
+/// Number of endpoints added with [`AddRoute::add`] so far.
+/// Incremented while holding the `ENDPOINTS` lock.
+static REGISTERED: AtomicUsize = AtomicUsize::new(0);
+
+/// The built openapi spec, and the number of endpoints registered when it was built.
+struct Built {
+    registered: usize,
+    snapshot: OpenApiSnapshot,
+}
+
+/// The built openapi spec. Read without locking: the lock is taken only to build the spec.
+static OPENAPI_BUILT: ArcSwapOption<Built> = ArcSwapOption::const_empty();
+static OPENAPI_BUILDING: Mutex<()> = Mutex::new(());
 
 /// Reset the openapi spec. Mostly used for testing.
 pub fn reset_openapi() {
+    let _building = OPENAPI_BUILDING.lock().unwrap();
     let mut endpoints = ENDPOINTS.lock().unwrap();
     *endpoints = vec![];
-    *OPENAPI_BUILT.lock().unwrap() = None;
-}
-
-/// Wrapper around the openapi spec.
-/// This wrapper is used to serialize the openapi spec.
-pub struct OpenApiWrapper<'a> {
-    guard: MutexGuard<'a, Option<utoipa::openapi::OpenApi>>,
-}
-impl Deref for OpenApiWrapper<'_> {
-    type Target = utoipa::openapi::OpenApi;
-
-    fn deref(&self) -> &Self::Target {
-        self.guard.as_ref().unwrap()
-    }
-}
-
-impl Serialize for OpenApiWrapper<'_> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        self.guard.as_ref().unwrap().serialize(serializer)
-    }
+    OPENAPI_BUILT.store(None);
 }
 
 /// Build the openapi spec.
-/// This function should be called after all the endpoints are defined.
-/// Because the openapi spec is cached, it's cheap to call this function multiple times.
+/// Because the openapi spec is cached as an immutable [`OpenApiSnapshot`], it's cheap to call this function multiple times:
+/// no lock is held while the spec is read or served.
 /// The `f` function is called only when the openapi spec is not built yet.
-pub fn build_openapi<'openapi, F>(f: F) -> OpenApiWrapper<'openapi>
+/// The schemas referenced by the endpoints are added to `components.schemas`,
+/// deduplicated by name. Components already defined by `f` take precedence.
+///
+/// In debug builds, panics if the spec references components which are not registered. See [`unresolved_refs`].
+///
+/// Endpoints added after the spec is built (e.g. by routers created lazily) are not lost:
+/// the next call merges them into the cached spec and returns a new snapshot.
+pub fn build_openapi<F>(f: F) -> OpenApiSnapshot
 where
     F: Fn() -> utoipa::openapi::OpenApiBuilder,
 {
-    let mut openapi = OPENAPI_BUILT.lock().unwrap();
-    if openapi.is_none() {
-        let mut endpoints = ENDPOINTS.lock().unwrap();
-
-        let paths = endpoints.drain(..).fold(Paths::default(), |mut acc, x| {
-            acc.merge(x);
-            acc
-        });
-        let openapi_builder = f().paths(paths);
+    if let Some(built) = up_to_date(OPENAPI_BUILT.load_full()) {
+        return built.snapshot.clone();
+    }
 
-        *openapi = Some(openapi_builder.build());
+    let building = OPENAPI_BUILDING.lock().unwrap();
+    // Another thread may have built the spec while waiting for the lock
+    let built = OPENAPI_BUILT.load_full();
+    if let Some(built) = up_to_date(built.clone()) {
+        return built.snapshot.clone();
     }
 
-    OpenApiWrapper { guard: openapi }
+    let mut endpoints = ENDPOINTS.lock().unwrap();
+    let registered = REGISTERED.load(Ordering::Acquire);
+    let base = match built {
+        Some(built) => built.snapshot.openapi().clone(),
+        None => f().build(),
+    };
+    let openapi = endpoints.drain(..).fold(base, |mut acc, x| {
+        acc.merge(x);
+        acc
+    });
+    drop(endpoints);
+
+    let snapshot = OpenApiSnapshot::new(openapi);
+    OPENAPI_BUILT.store(Some(Arc::new(Built {
+        registered,
+        snapshot: snapshot.clone(),
+    })));
+    drop(building);
+    refs::check_refs(snapshot.openapi());
+    snapshot
+}
+
+/// `built` if no endpoint was added after it was built.
+fn up_to_date(built: Option<Arc<Built>>) -> Option<Arc<Built>> {
+    built.filter(|built| built.registered == REGISTERED.load(Ordering::Acquire))
 }
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query},
+    http::StatusCode,
+    Form, Json,
+};
+use utoipa::{
+    openapi::{
+        encoding::Encoding,
+        path::{Operation, ParameterIn},
+        request_body::RequestBodyBuilder,
+        schema::Schema,
+        Components, ContentBuilder, RefOr, Required,
+    },
+    IntoParams, ToSchema,
+};
+
+use crate::__private::{binary_schema, OptionalArg};
+
+/// Document a handler argument (an extractor) in the openapi spec.
+///
+/// The `endpoint` macro calls [`OperationArg::document`] for every argument of the handler
+/// whose type implements this trait. Arguments which don't implement it are not documented,
+/// except `Json`, `Form`, `Query`, `Multipart` and `TypedHeader`, which fail to compile.
+///
+/// Implement this trait for your own `FromRequest`/`FromRequestParts` types:
+///
+/// ```rust
+/// use axum_openapi3::utoipa::openapi::{
+///     path::{Operation, ParameterBuilder, ParameterIn},
+///     Components, Required,
+/// };
+/// use axum_openapi3::utoipa::PartialSchema;
+/// use axum_openapi3::OperationArg;
+///
+/// struct Pagination {
+///     page: u64,
+/// }
+///
+/// impl OperationArg for Pagination {
+///     fn document(operation: &mut Operation, _components: &mut Components) {
+///         operation
+///             .parameters
+///             .get_or_insert_with(Vec::new)
+///             .push(
+///                 ParameterBuilder::new()
+///                     .parameter_in(ParameterIn::Query)
+///                     .name("page")
+///                     .required(Required::False)
+///                     .schema(Some(u64::schema()))
+///                     .build(),
+///             );
+///     }
+/// }
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be documented as a handler argument",
+    note = "`Json<T>` and `Form<T>` require `T: ToSchema`, `Query<T>` requires `T: IntoParams`",
+    note = "`Multipart` and `TypedHeader` require the `multipart` and `typed-header` features"
+)]
+pub trait OperationArg {
+    /// Add parameters, request body, security requirements or responses to `operation`.
+    /// The schemas referenced by them should be added to `components`.
+    fn document(operation: &mut Operation, components: &mut Components);
+}
+
+/// Documented as `application/json` request body.
+impl<T: ToSchema> OperationArg for Json<T> {
+    fn document(operation: &mut Operation, components: &mut Components) {
+        set_request_body(
+            operation,
+            "application/json",
+            Some(T::schema()),
+            BTreeMap::new(),
+        );
+        add_schemas::<T>(components);
+    }
+}
+
+/// Documented as `application/x-www-form-urlencoded` request body.
+/// The `endpoint` macro documents the `Form` of `GET` and `HEAD` handlers as query parameters instead.
+impl<T: ToSchema> OperationArg for Form<T> {
+    fn document(operation: &mut Operation, components: &mut Components) {
+        set_request_body(
+            operation,
+            "application/x-www-form-urlencoded",
+            Some(T::schema()),
+            BTreeMap::new(),
+        );
+        add_schemas::<T>(components);
+    }
+}
+
+/// Documented as `multipart/form-data` request body, with an object schema without properties.
+/// Declare the parts with `#[openapi(multipart = T)]` on the argument. See [`MultipartForm`].
+#[cfg(feature = "multipart")]
+impl OperationArg for axum::extract::Multipart {
+    fn document(operation: &mut Operation, _components: &mut Components) {
+        set_request_body(
+            operation,
+            "multipart/form-data",
+            Some(utoipa::openapi::ObjectBuilder::new().into()),
+            BTreeMap::new(),
+        );
+    }
+}
+
+/// Documented as `text/plain` request body.
+impl OperationArg for String {
+    fn document(operation: &mut Operation, _components: &mut Components) {
+        set_request_body(
+            operation,
+            "text/plain",
+            Some(<String as utoipa::PartialSchema>::schema()),
+            BTreeMap::new(),
+        );
+    }
+}
+
+/// Documented as `application/octet-stream` request body.
+impl OperationArg for Bytes {
+    fn document(operation: &mut Operation, _components: &mut Components) {
+        set_request_body(
+            operation,
+            "application/octet-stream",
+            Some(binary_schema()),
+            BTreeMap::new(),
+        );
+    }
+}
+
+/// Documented as `application/octet-stream` request body.
+impl OperationArg for Body {
+    fn document(operation: &mut Operation, _components: &mut Components) {
+        set_request_body(
+            operation,
+            "application/octet-stream",
+            Some(binary_schema()),
+            BTreeMap::new(),
+        );
+    }
+}
+
+/// Encodings of the parts of a `multipart/form-data` request body.
+///
+/// A `Multipart` argument annotated with `#[openapi(multipart = T)]` is documented
+/// with the schema of `T`, where binary file parts are `format = Binary` strings.
+/// If `T` implements this trait, the encodings of its parts are documented too,
+/// e.g. the content type of a file.
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+///
+/// use axum_openapi3::utoipa::openapi::encoding::{Encoding, EncodingBuilder};
+/// use axum_openapi3::utoipa::{self, ToSchema};
+/// use axum_openapi3::MultipartForm;
+///
+/// #[derive(ToSchema)]
+/// struct Upload {
+///     name: String,
+///     #[schema(value_type = String, format = Binary)]
+///     avatar: Vec<u8>,
+/// }
+///
+/// impl MultipartForm for Upload {
+///     fn encodings() -> BTreeMap<String, Encoding> {
+///         BTreeMap::from([(
+///             "avatar".to_string(),
+///             EncodingBuilder::new().content_type(Some("image/png")).build(),
+///         )])
+///     }
+/// }
+/// ```
+pub trait MultipartForm {
+    /// The encodings of the parts, by name.
+    fn encodings() -> BTreeMap<String, Encoding>;
+}
+
+/// Documented as query parameters.
+/// The schemas referenced by the fields are not known: declare them with `schemas(...)`.
+impl<T: IntoParams> OperationArg for Query<T> {
+    fn document(operation: &mut Operation, _components: &mut Components) {
+        operation
+            .parameters
+            .get_or_insert_with(Vec::new)
+            .extend(T::into_params(|| Some(ParameterIn::Query)));
+    }
+}
+
+/// Documented as a required header parameter, with a string schema.
+#[cfg(feature = "typed-header")]
+impl<T: axum_extra::headers::Header> OperationArg for axum_extra::TypedHeader<T> {
+    fn document(operation: &mut Operation, _components: &mut Components) {
+        operation
+            .parameters
+            .get_or_insert_with(Vec::new)
+            .push(crate::__private::param(
+                T::name().as_str(),
+                ParameterIn::Header,
+                true,
+                <String as utoipa::PartialSchema>::schema(),
+            ));
+    }
+}
+
+/// Documented as `T`, with its request body and parameters not required.
+impl<T: OperationArg> OperationArg for Option<T> {
+    fn document(operation: &mut Operation, components: &mut Components) {
+        let optional = OptionalArg::before(operation);
+        T::document(operation, components);
+        optional.after(operation);
+    }
+}
+
+/// Document the responses returned when an extractor rejects the request.
+///
+/// With `rejections = true`, the `endpoint` macro adds a response for every rejection
+/// of the arguments of the handler, unless the status is already documented.
+/// The responses share the `ExtractorRejection` schema: axum rejections are served as `text/plain`.
+///
+/// Implement this trait for your own `FromRequest`/`FromRequestParts` types:
+///
+/// ```rust
+/// use axum::http::StatusCode;
+/// use axum_openapi3::RejectionResponses;
+///
+/// struct ApiKey(String);
+///
+/// impl RejectionResponses for ApiKey {
+///     fn rejections() -> Vec<(StatusCode, &'static str)> {
+///         vec![(StatusCode::UNAUTHORIZED, "The API key is missing or invalid")]
+///     }
+/// }
+/// ```
+pub trait RejectionResponses {
+    /// The status codes of the rejections, with their description.
+    fn rejections() -> Vec<(StatusCode, &'static str)>;
+}
+
+const PAYLOAD_TOO_LARGE: (StatusCode, &str) = (
+    StatusCode::PAYLOAD_TOO_LARGE,
+    "The request body is too large",
+);
+
+impl<T> RejectionResponses for Json<T> {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![
+            (
+                StatusCode::BAD_REQUEST,
+                "The request body is not valid JSON",
+            ),
+            PAYLOAD_TOO_LARGE,
+            (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "The request body is not `application/json`",
+            ),
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The request body doesn't match the expected schema",
+            ),
+        ]
+    }
+}
+
+impl<T> RejectionResponses for Form<T> {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![
+            (StatusCode::BAD_REQUEST, "The form is not valid"),
+            PAYLOAD_TOO_LARGE,
+            (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "The request body is not `application/x-www-form-urlencoded`",
+            ),
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The request body doesn't match the expected schema",
+            ),
+        ]
+    }
+}
+
+impl<T> RejectionResponses for Query<T> {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![(
+            StatusCode::BAD_REQUEST,
+            "The query string doesn't match the expected parameters",
+        )]
+    }
+}
+
+impl<T> RejectionResponses for Path<T> {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![(
+            StatusCode::BAD_REQUEST,
+            "The path parameters don't match the expected types",
+        )]
+    }
+}
+
+impl RejectionResponses for String {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![
+            (
+                StatusCode::BAD_REQUEST,
+                "The request body is not valid UTF-8",
+            ),
+            PAYLOAD_TOO_LARGE,
+        ]
+    }
+}
+
+impl RejectionResponses for Bytes {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![PAYLOAD_TOO_LARGE]
+    }
+}
+
+#[cfg(feature = "multipart")]
+impl RejectionResponses for axum::extract::Multipart {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        crate::__private::MultipartRejections::rejections()
+    }
+}
+
+#[cfg(feature = "typed-header")]
+impl<T> RejectionResponses for axum_extra::TypedHeader<T> {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![(StatusCode::BAD_REQUEST, "The header is missing or invalid")]
+    }
+}
+
+/// Optional extractors reject the request when the value is present but invalid.
+impl<T: RejectionResponses> RejectionResponses for Option<T> {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        T::rejections()
+    }
+}
+
+/// Set a required request body with a single `content_type`.
+pub(crate) fn set_request_body(
+    operation: &mut Operation,
+    content_type: &str,
+    schema: Option<RefOr<Schema>>,
+    encodings: BTreeMap<String, Encoding>,
+) {
+    let content = encodings
+        .into_iter()
+        .fold(
+            ContentBuilder::new().schema(schema),
+            |content, (name, encoding)| content.encoding(name, encoding),
+        )
+        .build();
+    operation.request_body = Some(
+        RequestBodyBuilder::new()
+            .content(content_type, content)
+            .required(Some(Required::True))
+            .build(),
+    );
+}
+
+/// Add the schemas referenced by `T` to `components`. See `ToSchema::schemas`.
+pub(crate) fn add_schemas<T: ToSchema>(components: &mut Components) {
+    let mut schemas = vec![];
+    T::schemas(&mut schemas);
+    for (name, schema) in schemas {
+        components.schemas.entry(name).or_insert(schema);
+    }
+}
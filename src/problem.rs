@@ -0,0 +1,189 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{
+    openapi::{
+        schema::{AdditionalProperties, ObjectBuilder, Schema, SchemaFormat, Type},
+        ContentBuilder, Ref, RefOr, ResponseBuilder,
+    },
+    PartialSchema, ToSchema,
+};
+
+use crate::ErrorResponses;
+
+/// The content type of [`ProblemDetails`] responses.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An error body as defined by [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457),
+/// served as `application/problem+json`.
+///
+/// As the error type of a handler, the `endpoint` macro documents a `default` response
+/// referencing the `ProblemDetails` schema, which is added once to the components.
+/// Error types wrapping it can document their own responses with [`ProblemDetails::response`].
+///
+/// ```rust
+/// use axum::{http::StatusCode, Json};
+/// use axum_openapi3::{endpoint, ProblemDetails};
+///
+/// #[endpoint(method = "GET", path = "/todos/{id}")]
+/// async fn get_todo(id: axum::extract::Path<u64>) -> Result<Json<String>, ProblemDetails> {
+///     Err(ProblemDetails::new(StatusCode::NOT_FOUND)
+///         .detail(format!("No todo with id {}", id.0))
+///         .extension("id", id.0))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type. `about:blank` if missing.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub problem_type: Option<String>,
+    /// A short, human-readable summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The HTTP status code of the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference identifying this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension members, serialized alongside the standard ones.
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+impl ProblemDetails {
+    /// A problem with `status`, titled with its canonical reason (e.g. `Not Found`).
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            problem_type: None,
+            title: status.canonical_reason().map(str::to_string),
+            status: Some(status.as_u16()),
+            detail: None,
+            instance: None,
+            extensions: BTreeMap::new(),
+        }
+    }
+
+    /// Set the URI reference identifying the problem type.
+    pub fn problem_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = Some(problem_type.into());
+        self
+    }
+
+    /// Set the summary of the problem type.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the explanation of this occurrence of the problem.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set the URI reference identifying this occurrence of the problem.
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member. Values which cannot be serialized are stored as `null`.
+    pub fn extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or_default();
+        self.extensions.insert(name.into(), value);
+        self
+    }
+
+    /// An `application/problem+json` response described by `description`,
+    /// referencing the `ProblemDetails` schema.
+    /// The schema must be added to the components, e.g. by `ErrorResponses::schemas`.
+    pub fn response(description: &str) -> RefOr<utoipa::openapi::Response> {
+        ResponseBuilder::new()
+            .description(description)
+            .content(
+                PROBLEM_JSON,
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name(Self::name())))
+                    .build(),
+            )
+            .into()
+    }
+}
+
+/// Served with the status of the problem, `500 Internal Server Error` if missing or invalid.
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = self
+            .status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, [(header::CONTENT_TYPE, PROBLEM_JSON)], Json(self)).into_response()
+    }
+}
+
+impl PartialSchema for ProblemDetails {
+    fn schema() -> RefOr<Schema> {
+        let string = |description: &str| {
+            ObjectBuilder::new()
+                .schema_type(Type::String)
+                .description(Some(description))
+        };
+        ObjectBuilder::new()
+            .description(Some("A problem details object, as defined by RFC 9457"))
+            .property(
+                "type",
+                string("A URI reference identifying the problem type")
+                    .format(Some(SchemaFormat::Custom("uri-reference".to_string())))
+                    .default(Some("about:blank".into())),
+            )
+            .property(
+                "title",
+                string("A short, human-readable summary of the problem type"),
+            )
+            .property(
+                "status",
+                ObjectBuilder::new()
+                    .schema_type(Type::Integer)
+                    .description(Some("The HTTP status code of the response"))
+                    .minimum(Some(100))
+                    .maximum(Some(599)),
+            )
+            .property(
+                "detail",
+                string("A human-readable explanation specific to this occurrence of the problem"),
+            )
+            .property(
+                "instance",
+                string("A URI reference identifying this occurrence of the problem")
+                    .format(Some(SchemaFormat::Custom("uri-reference".to_string()))),
+            )
+            .additional_properties(Some(AdditionalProperties::<Schema>::FreeForm(true)))
+            .into()
+    }
+}
+
+impl ToSchema for ProblemDetails {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("ProblemDetails")
+    }
+}
+
+/// Documented as a `default` response, for handlers returning `Result<T, ProblemDetails>`.
+impl ErrorResponses for ProblemDetails {
+    fn responses() -> BTreeMap<String, RefOr<utoipa::openapi::Response>> {
+        BTreeMap::from([("default".to_string(), Self::response("Problem details"))])
+    }
+
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.push((Self::name().into_owned(), Self::schema()));
+    }
+}
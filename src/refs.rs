@@ -0,0 +1,49 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+use utoipa::openapi::OpenApi;
+
+/// The local references of `openapi` which don't resolve to a component,
+/// e.g. `#/components/schemas/Todo` when the `Todo` schema is not registered.
+///
+/// The `endpoint` macro cannot register the schemas referenced by the fields of `IntoParams` types
+/// and by the bodies of `IntoResponses` types: declare them with the `schemas(...)` argument
+/// or with `OpenApiRouter::schema`. In debug builds, `build_openapi` and `OpenApiRouter::build_openapi`
+/// panic if a reference doesn't resolve.
+pub fn unresolved_refs(openapi: &OpenApi) -> BTreeSet<String> {
+    let value = serde_json::to_value(openapi).expect("the openapi spec is always serializable");
+    let mut refs = BTreeSet::new();
+    collect_refs(&value, &mut refs);
+    refs.retain(|reference| {
+        reference
+            .strip_prefix('#')
+            .is_some_and(|pointer| value.pointer(pointer).is_none())
+    });
+    refs
+}
+
+fn collect_refs(value: &Value, refs: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(reference)) = object.get("$ref") {
+                refs.insert(reference.clone());
+            }
+            object.values().for_each(|value| collect_refs(value, refs));
+        }
+        Value::Array(array) => array.iter().for_each(|value| collect_refs(value, refs)),
+        _ => {}
+    }
+}
+
+/// Panic in debug builds if `openapi` has references which don't resolve.
+pub(crate) fn check_refs(openapi: &OpenApi) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let refs = unresolved_refs(openapi);
+    assert!(
+        refs.is_empty(),
+        "The openapi spec has unresolved references {refs:?}: declare the schemas \
+         with the `schemas(...)` argument of `endpoint` or with `OpenApiRouter::schema`"
+    );
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use utoipa::openapi::{schema::Schema, Header, RefOr, Response};
+
+/// Document the responses of an error type.
+///
+/// Implement this trait for the error type `E` of the handlers returning `Result<T, E>`:
+/// the `endpoint` macro adds these responses to the operation,
+/// alongside the success response documented from `T`.
+/// Error types which don't implement this trait don't add any response.
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+/// use axum_openapi3::utoipa::openapi::{
+///     schema::Schema, ContentBuilder, RefOr, Response, ResponseBuilder,
+/// };
+/// use axum_openapi3::utoipa::{PartialSchema, ToSchema};
+/// use axum_openapi3::ErrorResponses;
+///
+/// #[derive(ToSchema)]
+/// struct ErrorBody {
+///     message: String,
+/// }
+///
+/// enum AppError {
+///     NotFound,
+///     Internal,
+/// }
+///
+/// impl ErrorResponses for AppError {
+///     fn responses() -> BTreeMap<String, RefOr<Response>> {
+///         let content = ContentBuilder::new()
+///             .schema(Some(ErrorBody::schema()))
+///             .build();
+///         BTreeMap::from([
+///             (
+///                 "404".to_string(),
+///                 ResponseBuilder::new()
+///                     .description("Not found")
+///                     .content("application/json", content.clone())
+///                     .into(),
+///             ),
+///             (
+///                 "500".to_string(),
+///                 ResponseBuilder::new()
+///                     .description("Internal error")
+///                     .content("application/json", content)
+///                     .into(),
+///             ),
+///         ])
+///     }
+///
+///     fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+///         <ErrorBody as ToSchema>::schemas(schemas);
+///     }
+/// }
+/// ```
+pub trait ErrorResponses {
+    /// Return the responses, by status code.
+    fn responses() -> BTreeMap<String, RefOr<Response>>;
+
+    /// Push the schemas referenced by the responses. See `utoipa::ToSchema::schemas`.
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        let _ = schemas;
+    }
+}
+
+/// Document the headers set by a response type.
+///
+/// Implement this trait for the types in the return type of the handlers which set headers:
+/// the success type, or the elements of a tuple before the body, e.g. `(Location, Json<Todo>)`.
+/// The `endpoint` macro adds these headers to the success responses.
+/// Headers can also be declared with the `response_headers(...)` argument of `endpoint`.
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+/// use axum::response::{IntoResponseParts, ResponseParts};
+/// use axum_openapi3::utoipa::openapi::{Header, HeaderBuilder};
+/// use axum_openapi3::utoipa::PartialSchema;
+/// use axum_openapi3::ResponseHeaders;
+///
+/// struct TotalCount(u64);
+///
+/// impl IntoResponseParts for TotalCount {
+///     type Error = std::convert::Infallible;
+///
+///     fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+///         res.headers_mut().insert("x-total-count", self.0.into());
+///         Ok(res)
+///     }
+/// }
+///
+/// impl ResponseHeaders for TotalCount {
+///     fn headers() -> BTreeMap<String, Header> {
+///         BTreeMap::from([(
+///             "X-Total-Count".to_string(),
+///             HeaderBuilder::new().schema(u64::schema()).build(),
+///         )])
+///     }
+/// }
+/// ```
+pub trait ResponseHeaders {
+    /// Return the headers, by name.
+    fn headers() -> BTreeMap<String, Header>;
+}
+
+/// Documented as a header with a string schema.
+#[cfg(feature = "typed-header")]
+impl<T: axum_extra::headers::Header> ResponseHeaders for axum_extra::TypedHeader<T> {
+    fn headers() -> BTreeMap<String, Header> {
+        BTreeMap::from([(
+            T::name().to_string(),
+            utoipa::openapi::HeaderBuilder::new()
+                .schema(<String as utoipa::PartialSchema>::schema())
+                .build(),
+        )])
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use axum::Router;
+use utoipa::openapi::{
+    path::{Operation, ParameterBuilder, ParameterIn, PathItem},
+    security::{SecurityRequirement, SecurityScheme},
+    Components, OpenApi, OpenApiBuilder, Required,
+};
+use utoipa::ToSchema;
+
+use crate::{
+    docs::{self, DocsSpec},
+    operation::add_schemas,
+    refs, AddRoute, DocsConfig, Endpoint,
+};
+
+/// Router which owns its own openapi spec.
+///
+/// Unlike `Router::add`, which registers the endpoints in the global spec,
+/// every `OpenApiRouter` keeps track of the endpoints added to it.
+/// This allows to have more than one http server per process (e.g. a public and an admin API),
+/// each one with its own spec, and to build routers concurrently (e.g. in tests).
+pub struct OpenApiRouter<S = ()> {
+    router: Router<S>,
+    openapi: OpenApi,
+    /// Security requirements of the operations which don't declare their own.
+    security: Option<Vec<SecurityRequirement>>,
+    /// The specs served by `docs`, built from the final spec of the router.
+    docs: Vec<Arc<DocsSpec>>,
+}
+
+impl<S: Clone + Send + Sync + 'static> OpenApiRouter<S> {
+    /// Create a new router without any endpoint.
+    pub fn new() -> Self {
+        Self {
+            router: Router::new(),
+            openapi: OpenApiBuilder::new().build(),
+            security: None,
+            docs: vec![],
+        }
+    }
+
+    /// Add a route which is not documented in the openapi spec.
+    pub fn route(
+        self,
+        path: &str,
+        method_router: axum::routing::MethodRouter<S, std::convert::Infallible>,
+    ) -> Self {
+        Self {
+            router: self.router.route(path, method_router),
+            ..self
+        }
+    }
+
+    /// Nest a router at some path. See `Router::nest`.
+    /// The paths of the nested router are prefixed with `path` in the openapi spec.
+    /// The path parameters declared in `path` (e.g. `/orgs/{org_id}`) are documented
+    /// on every nested operation which doesn't already declare them.
+    pub fn nest(mut self, path: &str, router: OpenApiRouter<S>) -> Self {
+        self.merge_openapi(nest_openapi(path, router.openapi));
+        self.docs.extend(router.docs);
+        Self {
+            router: self.router.nest(path, router.router),
+            ..self
+        }
+    }
+
+    /// Merge the routes and the openapi spec of two routers. See `Router::merge`.
+    pub fn merge(mut self, other: OpenApiRouter<S>) -> Self {
+        self.merge_openapi(other.openapi);
+        self.docs.extend(other.docs);
+        Self {
+            router: self.router.merge(other.router),
+            ..self
+        }
+    }
+
+    /// Provide the state for the router. See `Router::with_state`.
+    pub fn with_state<S2>(self, state: S) -> OpenApiRouter<S2> {
+        OpenApiRouter {
+            router: self.router.with_state(state),
+            openapi: self.openapi,
+            security: self.security,
+            docs: self.docs,
+        }
+    }
+
+    /// Set the default security requirements: any of them must be satisfied.
+    /// They apply to the operations of this router, including the ones added, nested or merged later,
+    /// which don't declare their own (e.g. with the `security` argument of `endpoint`).
+    pub fn security(mut self, requirements: impl IntoIterator<Item = SecurityRequirement>) -> Self {
+        let requirements: Vec<_> = requirements.into_iter().collect();
+        apply_security(&mut self.openapi, &requirements);
+        self.security = Some(requirements);
+        self
+    }
+
+    /// Register a security scheme in the components of the openapi spec,
+    /// so security requirements can refer to it by `name`.
+    pub fn security_scheme(
+        mut self,
+        name: impl Into<String>,
+        scheme: impl Into<SecurityScheme>,
+    ) -> Self {
+        self.openapi
+            .components
+            .get_or_insert_with(Components::new)
+            .add_security_scheme(name, scheme);
+        self
+    }
+
+    /// Register the schema of `T`, and the schemas it references, in the components of the openapi spec.
+    /// Needed for the schemas referenced by responses which the `endpoint` macro doesn't build,
+    /// e.g. the bodies of a type deriving `IntoResponses`.
+    pub fn schema<T: ToSchema>(mut self) -> Self {
+        let components = self.openapi.components.get_or_insert_with(Components::new);
+        components
+            .schemas
+            .entry(T::name().into_owned())
+            .or_insert_with(T::schema);
+        add_schemas::<T>(components);
+        self
+    }
+
+    /// The openapi spec of the endpoints added to this router.
+    pub fn openapi(&self) -> &OpenApi {
+        &self.openapi
+    }
+
+    /// Build the openapi spec.
+    /// The paths and the schemas of the endpoints added to this router
+    /// are merged into the spec built by `builder`.
+    /// In debug builds, panics if the spec references components which are not registered.
+    /// See [`unresolved_refs`](crate::unresolved_refs).
+    pub fn build_openapi(&self, builder: OpenApiBuilder) -> OpenApi {
+        let openapi = builder.build().merge_from(self.openapi.clone());
+        refs::check_refs(&openapi);
+        openapi
+    }
+
+    /// Serve the openapi spec and the documentation UIs described by `config`.
+    /// The spec is built by `builder` when the router is converted into an axum router
+    /// (`into_router`, `into_parts` or `Router::from`): it includes the endpoints added after this call
+    /// and the ones of the routers this router is nested or merged into.
+    /// The documentation routes are not documented in the spec.
+    pub fn docs(mut self, builder: OpenApiBuilder, config: DocsConfig) -> Self {
+        let spec = DocsSpec::new(builder.build());
+        self.docs.push(spec.clone());
+        Self {
+            router: docs::mount(self.router, spec, &config),
+            ..self
+        }
+    }
+
+    /// Merge `openapi` into the spec of this router, applying the default security requirements.
+    fn merge_openapi(&mut self, mut openapi: OpenApi) {
+        if let Some(security) = &self.security {
+            apply_security(&mut openapi, security);
+        }
+        self.openapi.merge(openapi);
+    }
+
+    /// Return the underlying axum router.
+    pub fn into_router(self) -> Router<S> {
+        self.finish_docs();
+        self.router
+    }
+
+    /// Return the underlying axum router and the openapi spec of the endpoints.
+    pub fn into_parts(self) -> (Router<S>, OpenApi) {
+        self.finish_docs();
+        (self.router, self.openapi)
+    }
+}
+
+impl<S> OpenApiRouter<S> {
+    /// Build the specs served by `docs` from the final spec of this router.
+    fn finish_docs(&self) {
+        for spec in &self.docs {
+            spec.finish(&self.openapi);
+        }
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> AddRoute<S> for OpenApiRouter<S> {
+    fn add(mut self, endpoint: Endpoint<S>) -> Self {
+        self.merge_openapi(endpoint.openapi);
+        Self {
+            router: self.router.route(endpoint.path, endpoint.method_router),
+            ..self
+        }
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> Default for OpenApiRouter<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> From<OpenApiRouter<S>> for Router<S> {
+    fn from(router: OpenApiRouter<S>) -> Self {
+        router.finish_docs();
+        router.router
+    }
+}
+
+/// Prefix all the paths of `openapi` with `prefix`, as `Router::nest` does for the routes.
+fn nest_openapi(prefix: &str, mut openapi: OpenApi) -> OpenApi {
+    let prefix = prefix.trim_end_matches('/');
+    let prefix_params: Vec<&str> = prefix
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .collect();
+
+    let paths = std::mem::take(&mut openapi.paths.paths);
+    for (path, mut path_item) in paths {
+        let path = if path == "/" {
+            prefix.to_string()
+        } else {
+            format!("{prefix}{path}")
+        };
+
+        for operation in operations_mut(&mut path_item) {
+            add_path_params(operation, &prefix_params);
+        }
+
+        openapi.paths.paths.insert(path, path_item);
+    }
+
+    openapi
+}
+
+/// Set `requirements` on the operations without security requirements.
+fn apply_security(openapi: &mut OpenApi, requirements: &[SecurityRequirement]) {
+    for path_item in openapi.paths.paths.values_mut() {
+        for operation in operations_mut(path_item) {
+            operation
+                .security
+                .get_or_insert_with(|| requirements.to_vec());
+        }
+    }
+}
+
+fn add_path_params(operation: &mut Operation, names: &[&str]) {
+    if names.is_empty() {
+        return;
+    }
+    let parameters = operation.parameters.get_or_insert_with(Vec::new);
+    let missing: Vec<_> = names
+        .iter()
+        .filter(|name| {
+            !parameters
+                .iter()
+                .any(|p| p.parameter_in == ParameterIn::Path && p.name == **name)
+        })
+        .map(|name| {
+            ParameterBuilder::new()
+                .parameter_in(ParameterIn::Path)
+                .name(*name)
+                .required(Required::True)
+                .schema(Some(<String as utoipa::PartialSchema>::schema()))
+                .build()
+        })
+        .collect();
+    parameters.splice(0..0, missing);
+}
+
+fn operations_mut(path_item: &mut PathItem) -> impl Iterator<Item = &mut Operation> {
+    [
+        &mut path_item.get,
+        &mut path_item.put,
+        &mut path_item.post,
+        &mut path_item.delete,
+        &mut path_item.options,
+        &mut path_item.head,
+        &mut path_item.patch,
+        &mut path_item.trace,
+    ]
+    .into_iter()
+    .filter_map(Option::as_mut)
+}
@@ -0,0 +1,165 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::openapi::OpenApi;
+
+use crate::SpecFormat;
+
+/// Immutable snapshot of a built openapi spec, with the serialized documents cached.
+///
+/// Cloning a snapshot is cheap: the spec and its serializations are shared.
+/// Every format has a strong ETag, used to answer `If-None-Match` requests with `304 Not Modified`.
+///
+/// ```rust
+/// use axum::{http::HeaderMap, response::Response};
+/// use axum_openapi3::utoipa::openapi::OpenApiBuilder;
+/// use axum_openapi3::{build_openapi, SpecFormat};
+///
+/// async fn openapi(format: SpecFormat, headers: HeaderMap) -> Response {
+///     build_openapi(OpenApiBuilder::new).response(format, &headers)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenApiSnapshot {
+    inner: Arc<Snapshot>,
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    openapi: OpenApi,
+    json: Serialized,
+    #[cfg(feature = "yaml")]
+    yaml: Serialized,
+}
+
+#[derive(Debug)]
+struct Serialized {
+    bytes: Bytes,
+    etag: HeaderValue,
+}
+
+impl Serialized {
+    fn new(format: SpecFormat, openapi: &OpenApi) -> Self {
+        let bytes = Bytes::from(format.serialize(openapi));
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let etag = HeaderValue::from_str(&format!("\"{:016x}\"", hasher.finish()))
+            .expect("the etag is a valid header value");
+        Self { bytes, etag }
+    }
+}
+
+impl OpenApiSnapshot {
+    /// Serialize `openapi` in every supported format.
+    pub fn new(openapi: OpenApi) -> Self {
+        let json = Serialized::new(SpecFormat::Json, &openapi);
+        #[cfg(feature = "yaml")]
+        let yaml = Serialized::new(SpecFormat::Yaml, &openapi);
+        Self {
+            inner: Arc::new(Snapshot {
+                openapi,
+                json,
+                #[cfg(feature = "yaml")]
+                yaml,
+            }),
+        }
+    }
+
+    /// The openapi spec.
+    pub fn openapi(&self) -> &OpenApi {
+        &self.inner.openapi
+    }
+
+    /// The spec serialized in `format`.
+    pub fn bytes(&self, format: SpecFormat) -> &Bytes {
+        &self.serialized(format).bytes
+    }
+
+    /// The strong ETag of the spec serialized in `format`, quotes included.
+    pub fn etag(&self, format: SpecFormat) -> &HeaderValue {
+        &self.serialized(format).etag
+    }
+
+    /// Response serving the spec in `format`.
+    /// If the `If-None-Match` header of the request matches the ETag, the response is `304 Not Modified`.
+    /// Both carry `Vary: Accept`, as the format may be negotiated from the `Accept` header.
+    pub fn response(&self, format: SpecFormat, request_headers: &HeaderMap) -> Response {
+        let serialized = self.serialized(format);
+        let vary = (header::VARY, HeaderValue::from_static("accept"));
+        if if_none_match(request_headers, &serialized.etag) {
+            return (
+                StatusCode::NOT_MODIFIED,
+                [(header::ETAG, serialized.etag.clone()), vary],
+            )
+                .into_response();
+        }
+
+        (
+            StatusCode::OK,
+            [
+                (
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(format.content_type()),
+                ),
+                (header::ETAG, serialized.etag.clone()),
+                vary,
+            ],
+            serialized.bytes.clone(),
+        )
+            .into_response()
+    }
+
+    fn serialized(&self, format: SpecFormat) -> &Serialized {
+        match format {
+            SpecFormat::Json => &self.inner.json,
+            #[cfg(feature = "yaml")]
+            SpecFormat::Yaml => &self.inner.yaml,
+        }
+    }
+}
+
+/// Whether any of the ETags in the `If-None-Match` headers matches `etag`.
+/// `If-None-Match` uses the weak comparison, so `W/` prefixes are ignored.
+fn if_none_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let etag = etag.as_bytes();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/").as_bytes() == etag)
+}
+
+impl Deref for OpenApiSnapshot {
+    type Target = OpenApi;
+
+    fn deref(&self) -> &Self::Target {
+        self.openapi()
+    }
+}
+
+impl Serialize for OpenApiSnapshot {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.openapi().serialize(serializer)
+    }
+}
+
+/// Serve the cached JSON spec, without conditional requests support.
+impl IntoResponse for OpenApiSnapshot {
+    fn into_response(self) -> Response {
+        self.response(SpecFormat::Json, &HeaderMap::new())
+    }
+}
@@ -0,0 +1,150 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, MethodRouter},
+};
+use utoipa::openapi::OpenApi;
+
+use crate::OpenApiSnapshot;
+
+/// Format of the served openapi spec.
+///
+/// As an extractor, the format is taken from the `format` query parameter (`?format=json`,
+/// `?format=yaml`) or, if missing, negotiated from the `Accept` header. It defaults to JSON.
+/// YAML is available only with the `yaml` feature.
+///
+/// ```rust
+/// use axum::response::Response;
+/// use axum_openapi3::utoipa::openapi::OpenApiBuilder;
+/// use axum_openapi3::{build_openapi, SpecFormat};
+///
+/// async fn openapi(format: SpecFormat) -> Response {
+///     format.render(&build_openapi(OpenApiBuilder::new))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecFormat {
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl SpecFormat {
+    /// The content type of the format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SpecFormat::Json => "application/json",
+            #[cfg(feature = "yaml")]
+            SpecFormat::Yaml => "application/yaml",
+        }
+    }
+
+    /// Serialize `openapi` in this format.
+    pub fn serialize(&self, openapi: &OpenApi) -> String {
+        match self {
+            SpecFormat::Json => openapi
+                .to_json()
+                .expect("the openapi spec is always serializable"),
+            #[cfg(feature = "yaml")]
+            SpecFormat::Yaml => openapi
+                .to_yaml()
+                .expect("the openapi spec is always serializable"),
+        }
+    }
+
+    /// Serialize `openapi` in this format into a response with the right content type.
+    pub fn render(&self, openapi: &OpenApi) -> Response {
+        (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, self.content_type())],
+            self.serialize(openapi),
+        )
+            .into_response()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(SpecFormat::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(SpecFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" | "application/*" | "*/*" => Some(SpecFormat::Json),
+            #[cfg(feature = "yaml")]
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(SpecFormat::Yaml)
+            }
+            _ => None,
+        }
+    }
+
+    fn from_query(query: &str) -> Option<Self> {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "format")
+            .and_then(|(_, value)| Self::from_name(&value.to_ascii_lowercase()))
+    }
+
+    /// The supported format with the highest quality in the `Accept` header.
+    fn from_accept(headers: &HeaderMap) -> Option<Self> {
+        let mut best: Option<(Self, f32)> = None;
+        for accept in headers.get_all(header::ACCEPT) {
+            let Ok(accept) = accept.to_str() else {
+                continue;
+            };
+            for media_range in accept.split(',') {
+                let mut params = media_range.split(';').map(str::trim);
+                let media_type = params.next().unwrap_or_default().to_ascii_lowercase();
+                let quality = params
+                    .filter_map(|param| param.strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+
+                let Some(format) = Self::from_media_type(&media_type) else {
+                    continue;
+                };
+                if quality > 0.0 && best.is_none_or(|(_, q)| quality > q) {
+                    best = Some((format, quality));
+                }
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+
+    fn negotiate(parts: &Parts) -> Self {
+        parts
+            .uri
+            .query()
+            .and_then(Self::from_query)
+            .or_else(|| Self::from_accept(&parts.headers))
+            .unwrap_or(SpecFormat::Json)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SpecFormat {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::negotiate(parts))
+    }
+}
+
+/// Handler serving `openapi` in the format requested by the client. See [`SpecFormat`].
+/// The spec is serialized once, when this function is called, and served with an ETag:
+/// build it after adding all the endpoints. `OpenApiRouter::docs` builds its spec lazily instead.
+pub fn spec_handler<S: Clone + Send + Sync + 'static>(
+    openapi: &OpenApi,
+) -> MethodRouter<S, Infallible> {
+    let snapshot = OpenApiSnapshot::new(openapi.clone());
+    get(
+        move |format: SpecFormat, headers: HeaderMap| async move { snapshot.response(format, &headers) },
+    )
+}
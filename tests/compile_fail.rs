@@ -0,0 +1,7 @@
+/// The `endpoint` macro rejects invalid path templates and `Path` extractors
+/// which don't match them, pointing at the path literal or at the argument.
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -1,21 +1,36 @@
 #![allow(dead_code)]
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use axum::{
-    extract::{Path, Query, State},
+    body::Body,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request, State},
+    http::{header, request::Parts, StatusCode},
     response::IntoResponse,
     Json, Router,
 };
 use axum_openapi3::utoipa::{
-    openapi::{Paths, RefOr, Schema},
+    openapi::{
+        path::Operation,
+        schema::{
+            ArrayBuilder, ArrayItems, KnownFormat, ObjectBuilder, OneOfBuilder, SchemaFormat, Type,
+        },
+        security::SecurityRequirement,
+        Components, ContentBuilder, Paths, RefOr, Response, ResponseBuilder, Schema,
+    },
     IntoParams, PartialSchema, ToSchema,
 };
 use axum_openapi3::*;
 use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
 use utoipa::openapi::{
-    path::{Parameter, ParameterBuilder, ParameterIn},
-    OpenApiBuilder, Required,
+    encoding::{Encoding, EncodingBuilder},
+    path::{HttpMethod, Parameter, ParameterBuilder, ParameterIn},
+    security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Deprecated, OpenApiBuilder, Required,
 };
 
 struct MyState;
@@ -32,8 +47,10 @@ pub fn get_router() -> Router {
         .add(mark_todo_as_complete())
         .add(filter())
         .add(get_todo())
+        .add(todo_responses())
         .add(mark_todo_as())
-        .add(generic());
+        .add(generic())
+        .add(get_todos_with_tags());
 
     let router2: Router = Router::new()
         .add(query_and_json())
@@ -112,6 +129,241 @@ async fn mark_todo_as(_: Path<u64>, _: Json<bool>) -> Json<Todo> {
     unreachable!("")
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+struct Tag {
+    name: String,
+}
+#[derive(Serialize, Deserialize, ToSchema)]
+struct TodoWithTags {
+    id: u64,
+    tags: Vec<Tag>,
+}
+#[endpoint(
+    method = "GET",
+    path = "/todos-with-tags",
+    description = "List todos with tags"
+)]
+async fn get_todos_with_tags() -> Json<Vec<TodoWithTags>> {
+    unreachable!("")
+}
+
+#[derive(Serialize, ToSchema)]
+struct ErrorBody {
+    message: String,
+}
+enum AppError {
+    NotFound,
+}
+impl IntoResponse for AppError {
+    fn into_response(self) -> axum::response::Response {
+        unreachable!("")
+    }
+}
+impl ErrorResponses for AppError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        BTreeMap::from([(
+            "404".to_string(),
+            ResponseBuilder::new()
+                .description("Not found")
+                .content(
+                    "application/json",
+                    ContentBuilder::new()
+                        .schema(Some(ErrorBody::schema()))
+                        .build(),
+                )
+                .into(),
+        )])
+    }
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.push((ErrorBody::name().into(), ErrorBody::schema()));
+    }
+}
+#[endpoint(method = "GET", path = "/fallible/{id}", description = "Fallible")]
+async fn fallible(_: Path<u64>) -> Result<Json<Todo>, AppError> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "GET",
+    path = "/fallible-status",
+    description = "Fallible with status code"
+)]
+async fn fallible_status() -> Result<Json<Vec<Todo>>, StatusCode> {
+    unreachable!("")
+}
+
+struct AuthUser;
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(_: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        unreachable!("")
+    }
+}
+impl OperationArg for AuthUser {
+    fn document(operation: &mut Operation, _: &mut Components) {
+        operation
+            .security
+            .get_or_insert_with(Vec::new)
+            .push(SecurityRequirement::new("bearer", Vec::<String>::new()));
+        operation.responses.responses.insert(
+            "401".to_string(),
+            ResponseBuilder::new().description("Unauthorized").into(),
+        );
+    }
+}
+struct NotDocumented;
+impl<S: Send + Sync> FromRequestParts<S> for NotDocumented {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(_: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        unreachable!("")
+    }
+}
+#[endpoint(method = "POST", path = "/custom-extractors", description = "")]
+async fn custom_extractors(_: AuthUser, _: NotDocumented, _: Json<Todo>) -> Json<Todo> {
+    unreachable!("")
+}
+
+#[endpoint(method = "POST", path = "/map", description = "Map")]
+async fn map(_: Json<HashMap<String, u64>>) -> Json<BTreeMap<String, Vec<Todo>>> {
+    unreachable!("")
+}
+#[endpoint(method = "POST", path = "/tuple", description = "Tuple")]
+async fn tuple(_: Json<(u64, String)>) -> Json<[u8; 32]> {
+    unreachable!("")
+}
+#[endpoint(method = "POST", path = "/nested_tuple", description = "Nested tuple")]
+async fn nested_tuple(_: Json<Vec<(u64, String)>>) -> Json<Option<(u64, Box<[Tag; 2]>)>> {
+    unreachable!("")
+}
+#[endpoint(method = "POST", path = "/unit", description = "Unit")]
+async fn unit(_: Json<(TodoWithTags, [u64; 2])>) -> Json<()> {
+    unreachable!("")
+}
+
+#[endpoint(method = "POST", path = "/qualified/{id}", description = "Qualified")]
+async fn qualified(
+    _: axum::extract::Path<u64>,
+    _: axum::extract::Query<TodoFilter>,
+    _: axum::extract::State<Arc<MyState>>,
+    _: axum::Json<Todo>,
+) -> std::result::Result<axum::Json<Todo>, AppError> {
+    unreachable!("")
+}
+
+type AppState = State<Arc<MyState>>;
+type TodoPath = Path<u64>;
+struct ValidatedJson<T>(T);
+impl<S: Send + Sync, T> FromRequest<S> for ValidatedJson<T> {
+    type Rejection = StatusCode;
+
+    async fn from_request(_: Request, _: &S) -> Result<Self, Self::Rejection> {
+        unreachable!("")
+    }
+}
+#[endpoint(method = "POST", path = "/annotated/{id}", description = "Annotated")]
+async fn annotated(
+    #[openapi(state = Arc<MyState>)] _: AppState,
+    #[openapi(path = u64)] _: TodoPath,
+    #[openapi(skip)] _: Query<TodoFilter>,
+    #[openapi(body)] _: ValidatedJson<Todo>,
+) -> Json<Todo> {
+    unreachable!("")
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/orgs/{org}/users/{user}",
+    description = "Tuple path"
+)]
+async fn tuple_path(_: Path<(u64, String)>) -> Json<Todo> {
+    unreachable!("")
+}
+#[derive(Deserialize, IntoParams)]
+struct UserParams {
+    org: u64,
+    user: String,
+}
+#[endpoint(
+    method = "GET",
+    path = "/orgs/{org}/members/{user}",
+    description = "Struct path"
+)]
+async fn struct_path(_: Path<UserParams>) -> Json<Todo> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "GET",
+    path = "/orgs/{org}/teams/{team}",
+    description = "Map path"
+)]
+async fn map_path(Path(params): Path<HashMap<String, String>>) -> String {
+    format!("{}/{}", params["org"], params["team"])
+}
+#[derive(Deserialize, ToSchema)]
+struct ProjectParams {
+    org: u64,
+    project: String,
+}
+#[endpoint(
+    method = "GET",
+    path = "/orgs/{org}/projects/{project}",
+    description = "Schema path"
+)]
+async fn schema_path(_: Path<ProjectParams>) -> Json<Todo> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "GET",
+    path = "/orgs/{org}/projects/{slug}",
+    description = "Unknown placeholder"
+)]
+async fn unknown_placeholder_path(_: Path<ProjectParams>) -> Json<Todo> {
+    unreachable!("")
+}
+
+#[derive(Deserialize, ToSchema)]
+enum IssueKind {
+    Bug,
+    Feature,
+}
+#[derive(Deserialize, IntoParams)]
+struct IssueFilter {
+    kind: IssueKind,
+}
+#[derive(Deserialize, IntoParams)]
+struct IssuePath {
+    kind: IssueKind,
+    id: u64,
+}
+#[endpoint(
+    method = "GET",
+    path = "/issues",
+    description = "Issues by kind",
+    schemas(IssueKind)
+)]
+async fn issues(_: Query<IssueFilter>) -> Json<Vec<Todo>> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "GET",
+    path = "/issues/{kind}/{id}",
+    description = "Issue",
+    schemas(IssueKind)
+)]
+async fn issue(_: Path<IssuePath>) -> Json<Todo> {
+    unreachable!("")
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/undeclared-issues",
+    description = "Issues without their schemas"
+)]
+async fn undeclared_issues(_: Query<IssueFilter>) -> Json<Vec<Todo>> {
+    unreachable!("")
+}
+
 #[derive(Deserialize, IntoParams)]
 struct QueryParams {
     #[serde(rename = "api-key")]
@@ -138,6 +390,14 @@ fn test_all() {
     _ = get_router();
 
     let openapi = build_openapi(OpenApiBuilder::new);
+    // The spec is built once, following calls return the cached snapshot
+    let cached = build_openapi(|| unreachable!("the spec is already built"));
+    assert_eq!(
+        cached.bytes(SpecFormat::Json),
+        openapi.bytes(SpecFormat::Json)
+    );
+
+    assert_no_dangling_refs(&openapi);
 
     let paths = &openapi.paths;
 
@@ -270,6 +530,1721 @@ fn test_all() {
             .schema(Some(String::schema()))
             .build()]),
     );
+
+    assert_endpoint(
+        paths,
+        "/todos-with-tags",
+        "get",
+        "get_todos_with_tags",
+        "List todos with tags",
+        Some(Vec::<TodoWithTags>::schema()),
+        None,
+        None,
+        None,
+    );
+
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert_eq!(schemas.get("Tag"), Some(&Tag::schema()));
+
+    // Endpoints added after the spec is built are merged into the cached spec
+    let _: Router = Router::new().add(late());
+    let openapi = build_openapi(|| unreachable!("the spec is already built"));
+    assert!(openapi.paths.paths.contains_key("/todos-with-tags"));
+    assert_endpoint(
+        &openapi.paths,
+        "/late",
+        "get",
+        "late",
+        "Added after the spec is built",
+        Some(u64::schema()),
+        None,
+        None,
+        None,
+    );
+    assert_ne!(
+        cached.etag(SpecFormat::Json),
+        openapi.etag(SpecFormat::Json)
+    );
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/late",
+    description = "Added after the spec is built"
+)]
+async fn late() -> Json<u64> {
+    unreachable!("")
+}
+
+#[test]
+fn test_openapi_router() {
+    let public: OpenApiRouter = OpenApiRouter::new()
+        .add(get_todos())
+        .add(get_todos_with_tags());
+    let admin: OpenApiRouter<Arc<MyState>> = OpenApiRouter::new().add(query_and_json());
+
+    let public_openapi = public.build_openapi(OpenApiBuilder::new());
+    let admin_openapi = admin.build_openapi(OpenApiBuilder::new());
+
+    let public_paths: Vec<_> = public_openapi.paths.paths.keys().collect();
+    assert_eq!(public_paths, vec!["/todos", "/todos-with-tags"]);
+    let admin_paths: Vec<_> = admin_openapi.paths.paths.keys().collect();
+    assert_eq!(admin_paths, vec!["/query-and-json/{id}"]);
+
+    assert_endpoint(
+        &public_openapi.paths,
+        "/todos",
+        "get",
+        "get_todos",
+        "List all todos",
+        Some(Vec::<Todo>::schema()),
+        None,
+        None,
+        None,
+    );
+
+    let public_schemas = &public_openapi.components.as_ref().unwrap().schemas;
+    assert!(public_schemas.contains_key("Tag"));
+    let admin_schemas = &admin_openapi.components.as_ref().unwrap().schemas;
+    assert!(!admin_schemas.contains_key("Tag"));
+
+    let _: Router = admin.with_state(Arc::new(MyState)).into();
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/todos/{id}",
+    path_prefix = "/orgs/{org_id}",
+    description = "Get todo by id"
+)]
+async fn get_org_todo(Path((_org_id, _id)): Path<(String, u64)>) -> Json<Todo> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "GET",
+    path = "/",
+    path_prefix = "/orgs/{org_id}/",
+    description = "Get org"
+)]
+async fn get_org(Path(org_id): Path<String>) -> String {
+    org_id
+}
+
+#[tokio::test]
+async fn test_openapi_router_nest_and_merge() {
+    let todos: OpenApiRouter = OpenApiRouter::new()
+        .add(get_todos())
+        .add(get_org_todo())
+        .add(get_org());
+    let strings: OpenApiRouter = OpenApiRouter::new().add(get_list_string());
+    let root: OpenApiRouter = OpenApiRouter::new().add(static_str());
+
+    let router = OpenApiRouter::new()
+        .nest("/orgs/{org_id}", todos)
+        .nest("/v1", strings)
+        .merge(root);
+
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+    let paths: Vec<_> = openapi.paths.paths.keys().collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/",
+            "/orgs/{org_id}",
+            "/orgs/{org_id}/todos",
+            "/orgs/{org_id}/todos/{id}",
+            "/v1/strings"
+        ]
+    );
+
+    let org_id = ParameterBuilder::new()
+        .parameter_in(ParameterIn::Path)
+        .name("org_id")
+        .required(Required::True)
+        .schema(Some(String::schema()))
+        .build();
+    assert_endpoint(
+        &openapi.paths,
+        "/orgs/{org_id}/todos",
+        "get",
+        "get_todos",
+        "List all todos",
+        Some(Vec::<Todo>::schema()),
+        None,
+        None,
+        Some(vec![org_id.clone()]),
+    );
+    assert_endpoint(
+        &openapi.paths,
+        "/orgs/{org_id}/todos/{id}",
+        "get",
+        "get_org_todo",
+        "Get todo by id",
+        Some(Todo::schema()),
+        None,
+        None,
+        Some(vec![
+            org_id,
+            ParameterBuilder::new()
+                .parameter_in(ParameterIn::Path)
+                .name("id")
+                .required(Required::True)
+                .schema(Some(u64::schema()))
+                .build(),
+        ]),
+    );
+    assert_endpoint(
+        &openapi.paths,
+        "/v1/strings",
+        "get",
+        "get_list_string",
+        "List all string",
+        Some(Vec::<String>::schema()),
+        None,
+        None,
+        None,
+    );
+    // The endpoint documents the prefix placeholder, so it's not added twice
+    let operation = openapi.paths.paths["/orgs/{org_id}"].get.as_ref().unwrap();
+    let names: Vec<_> = operation
+        .parameters
+        .iter()
+        .flatten()
+        .map(|parameter| parameter.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["org_id"]);
+
+    let (_, body) = get(&router.into_router(), "/orgs/acme").await;
+    assert_eq!(body, "acme");
+}
+
+#[test]
+fn test_result_return_type() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(fallible()).add(fallible_status());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    assert_endpoint(
+        &openapi.paths,
+        "/fallible/{id}",
+        "get",
+        "fallible",
+        "Fallible",
+        Some(Todo::schema()),
+        None,
+        None,
+        Some(vec![ParameterBuilder::new()
+            .parameter_in(ParameterIn::Path)
+            .name("id")
+            .required(Required::True)
+            .schema(Some(u64::schema()))
+            .build()]),
+    );
+    let operation = openapi.paths.paths["/fallible/{id}"].get.as_ref().unwrap();
+    let responses: Vec<_> = operation.responses.responses.keys().collect();
+    assert_eq!(responses, vec!["200", "404"]);
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert!(schemas.contains_key("ErrorBody"));
+
+    assert_endpoint(
+        &openapi.paths,
+        "/fallible-status",
+        "get",
+        "fallible_status",
+        "Fallible with status code",
+        Some(Vec::<Todo>::schema()),
+        None,
+        None,
+        None,
+    );
+    let operation = openapi.paths.paths["/fallible-status"]
+        .get
+        .as_ref()
+        .unwrap();
+    let responses: Vec<_> = operation.responses.responses.keys().collect();
+    assert_eq!(responses, vec!["200"]);
+}
+
+#[endpoint(
+    method = "POST",
+    path = "/created",
+    description = "Create a todo",
+    status = 201,
+    summary = "Create",
+    tags = ["todos", "write"],
+    deprecated = true,
+    operation_id = "createTodo"
+)]
+async fn created(_: Json<Todo>) -> Json<Todo> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "DELETE",
+    path = "/created",
+    description = "Delete",
+    status = "204"
+)]
+async fn no_content() -> StatusCode {
+    StatusCode::NO_CONTENT
+}
+
+#[test]
+fn test_endpoint_arguments() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(created()).add(no_content());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let operation = openapi.paths.paths["/created"].post.as_ref().unwrap();
+    assert_eq!(operation.operation_id.as_deref(), Some("createTodo"));
+    assert_eq!(operation.summary.as_deref(), Some("Create"));
+    assert_eq!(
+        operation.tags,
+        Some(vec!["todos".to_string(), "write".to_string()])
+    );
+    assert_eq!(operation.deprecated, Some(Deprecated::True));
+    let responses: Vec<_> = operation.responses.responses.keys().collect();
+    assert_eq!(responses, vec!["201"]);
+    let response = resolve_as_t(&operation.responses.responses["201"]);
+    assert_eq!(
+        response.content["application/json"].schema,
+        Some(Todo::schema())
+    );
+
+    let operation = openapi.paths.paths["/created"].delete.as_ref().unwrap();
+    assert_eq!(operation.operation_id.as_deref(), Some("no_content"));
+    assert_eq!(operation.summary, None);
+    assert_eq!(operation.tags, None);
+    assert_eq!(operation.deprecated, None);
+    let response = resolve_as_t(&operation.responses.responses["204"]);
+    assert!(response.content.is_empty());
+}
+
+/// Get a todo
+/// by id
+///
+/// Returns the todo with the given `id`:
+/// - `completed` is `false` for new todos
+#[endpoint(method = "GET", path = "/documented/{id}")]
+async fn documented(_: Path<u64>) -> Json<Todo> {
+    unreachable!("")
+}
+/// Get all todos
+///
+/// Overridden description
+#[endpoint(
+    method = "GET",
+    path = "/documented",
+    summary = "Explicit summary",
+    description = "Explicit description"
+)]
+async fn documented_explicit() -> Json<Vec<Todo>> {
+    unreachable!("")
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/secure",
+    description = "Scoped",
+    security = [("oauth2" = ["todos:read", "todos:write"]), "api_key"]
+)]
+async fn scoped() -> Json<u64> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "POST",
+    path = "/secure",
+    description = "Both",
+    security = [("oauth2" = [], "api_key" = []), ()]
+)]
+async fn both_schemes() -> Json<u64> {
+    unreachable!("")
+}
+#[endpoint(method = "GET", path = "/public", description = "Public", security = [])]
+async fn public() -> Json<u64> {
+    unreachable!("")
+}
+#[endpoint(method = "GET", path = "/default", description = "Default security")]
+async fn default_security() -> Json<u64> {
+    unreachable!("")
+}
+
+#[test]
+fn test_security() {
+    let bearer = SecurityRequirement::new("bearer", Vec::<String>::new());
+    let nested = OpenApiRouter::new().add(default_security());
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        )
+        .security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        )
+        .add(scoped())
+        .add(default_security())
+        .security([bearer.clone()])
+        .add(both_schemes())
+        .add(public())
+        .nest("/v1", nested);
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let security_schemes = &openapi.components.as_ref().unwrap().security_schemes;
+    assert_eq!(
+        security_schemes.keys().collect::<Vec<_>>(),
+        vec!["api_key", "bearer"]
+    );
+
+    let operation = openapi.paths.paths["/secure"].get.as_ref().unwrap();
+    assert_eq!(
+        operation.security,
+        Some(vec![
+            SecurityRequirement::new("oauth2", ["todos:read", "todos:write"]),
+            SecurityRequirement::new("api_key", Vec::<String>::new()),
+        ])
+    );
+    let operation = openapi.paths.paths["/secure"].post.as_ref().unwrap();
+    assert_eq!(
+        operation.security,
+        Some(vec![
+            SecurityRequirement::new("oauth2", Vec::<String>::new())
+                .add("api_key", Vec::<String>::new()),
+            SecurityRequirement::default(),
+        ])
+    );
+    let operation = openapi.paths.paths["/public"].get.as_ref().unwrap();
+    assert_eq!(operation.security, Some(vec![]));
+
+    // The default applies to the endpoints added before and after it
+    let operation = openapi.paths.paths["/default"].get.as_ref().unwrap();
+    assert_eq!(operation.security, Some(vec![bearer.clone()]));
+    let operation = openapi.paths.paths["/v1/default"].get.as_ref().unwrap();
+    assert_eq!(operation.security, Some(vec![bearer]));
+}
+
+#[endpoint(
+    method = "POST",
+    path = "/headers",
+    description = "Headers and cookies",
+    headers("X-Tenant-Id": u64, "Idempotency-Key", "X-Tag": Option<Tag>),
+    cookies("session")
+)]
+async fn headers_and_cookies() -> Json<u64> {
+    unreachable!("")
+}
+
+#[test]
+fn test_header_and_cookie_params() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(headers_and_cookies());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let param = |name: &str, parameter_in, required, schema| {
+        ParameterBuilder::new()
+            .name(name)
+            .parameter_in(parameter_in)
+            .required(required)
+            .schema(Some(schema))
+            .build()
+    };
+    let operation = openapi.paths.paths["/headers"].post.as_ref().unwrap();
+    assert_eq!(
+        operation.parameters,
+        Some(vec![
+            param(
+                "X-Tenant-Id",
+                ParameterIn::Header,
+                Required::True,
+                u64::schema()
+            ),
+            param(
+                "Idempotency-Key",
+                ParameterIn::Header,
+                Required::True,
+                String::schema()
+            ),
+            param("X-Tag", ParameterIn::Header, Required::False, Tag::schema()),
+            param(
+                "session",
+                ParameterIn::Cookie,
+                Required::True,
+                String::schema()
+            ),
+        ])
+    );
+}
+
+#[cfg(feature = "typed-header")]
+#[endpoint(method = "GET", path = "/typed-header", description = "Typed header")]
+async fn typed_header(_: axum_extra::TypedHeader<axum_extra::headers::UserAgent>) -> Json<u64> {
+    unreachable!("")
+}
+
+#[cfg(feature = "typed-header")]
+#[test]
+fn test_typed_header() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(typed_header());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let operation = openapi.paths.paths["/typed-header"].get.as_ref().unwrap();
+    assert_eq!(
+        operation.parameters,
+        Some(vec![ParameterBuilder::new()
+            .name("user-agent")
+            .parameter_in(ParameterIn::Header)
+            .required(Required::True)
+            .schema(Some(String::schema()))
+            .build()])
+    );
+}
+
+#[endpoint(method = "POST", path = "/form", description = "Form")]
+async fn form(_: axum::Form<Todo>) -> Json<u64> {
+    unreachable!("")
+}
+type LegacyForm<T> = axum::Form<T>;
+#[endpoint(method = "PUT", path = "/form", description = "Aliased form")]
+async fn aliased_form(#[openapi(form)] _: LegacyForm<TodoWithTags>) -> Json<u64> {
+    unreachable!("")
+}
+#[derive(Deserialize, ToSchema)]
+struct SearchForm {
+    q: String,
+    page: Option<u64>,
+}
+#[endpoint(method = "GET", path = "/search", description = "Search form")]
+async fn search_form(axum::Form(form): axum::Form<SearchForm>) -> String {
+    form.q
+}
+
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct Upload {
+    name: String,
+    #[schema(value_type = String, format = Binary)]
+    avatar: Vec<u8>,
+}
+impl MultipartForm for Upload {
+    fn encodings() -> BTreeMap<String, Encoding> {
+        BTreeMap::from([(
+            "avatar".to_string(),
+            EncodingBuilder::new()
+                .content_type(Some("image/png"))
+                .build(),
+        )])
+    }
+}
+
+#[cfg(feature = "multipart")]
+#[endpoint(method = "POST", path = "/upload", description = "Upload")]
+async fn upload(#[openapi(multipart = Upload)] _: axum::extract::Multipart) -> Json<u64> {
+    unreachable!("")
+}
+#[cfg(feature = "multipart")]
+#[endpoint(method = "PUT", path = "/upload", description = "Undeclared upload")]
+async fn undeclared_upload(_: axum::extract::Multipart) -> Json<u64> {
+    unreachable!("")
+}
+
+#[tokio::test]
+async fn test_form_and_multipart_bodies() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(form())
+        .add(aliased_form())
+        .add(search_form());
+    #[cfg(feature = "multipart")]
+    let router = router.add(upload()).add(undeclared_upload());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let body = |path: &str, method: HttpMethod| {
+        let path_item = &openapi.paths.paths[path];
+        let operation = match method {
+            HttpMethod::Post => path_item.post.as_ref(),
+            HttpMethod::Put => path_item.put.as_ref(),
+            _ => unreachable!(),
+        };
+        let body = operation.unwrap().request_body.clone().unwrap();
+        assert_eq!(body.required, Some(Required::True));
+        body.content
+    };
+
+    let content = body("/form", HttpMethod::Post);
+    assert_eq!(
+        content["application/x-www-form-urlencoded"].schema,
+        Some(Todo::schema())
+    );
+    let content = body("/form", HttpMethod::Put);
+    assert_eq!(
+        content["application/x-www-form-urlencoded"].schema,
+        Some(TodoWithTags::schema())
+    );
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert!(schemas.contains_key("Tag"));
+
+    // axum reads the form of a GET request from the query string
+    let operation = openapi.paths.paths["/search"].get.as_ref().unwrap();
+    assert_eq!(operation.request_body, None);
+    let RefOr::T(Schema::Object(search)) = SearchForm::schema() else {
+        unreachable!()
+    };
+    let param = |name: &str, required: Required| {
+        ParameterBuilder::new()
+            .parameter_in(ParameterIn::Query)
+            .name(name)
+            .required(required)
+            .schema(Some(search.properties[name].clone()))
+            .build()
+    };
+    assert_eq!(
+        operation.parameters,
+        Some(vec![
+            param("page", Required::False),
+            param("q", Required::True)
+        ])
+    );
+
+    #[cfg(feature = "multipart")]
+    {
+        let content = body("/upload", HttpMethod::Post);
+        let multipart = &content["multipart/form-data"];
+        assert_eq!(multipart.schema, Some(Upload::schema()));
+        assert_eq!(multipart.encoding, Upload::encodings());
+
+        let content = body("/upload", HttpMethod::Put);
+        assert!(content.contains_key("multipart/form-data"));
+    }
+
+    let (_, text) = get(&router.into_router(), "/search?q=todo").await;
+    assert_eq!(text, "todo");
+}
+
+#[endpoint(
+    method = "POST",
+    path = "/optional/json",
+    description = "Optional JSON"
+)]
+async fn optional_json(_: Option<Json<Todo>>) -> Json<u64> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "PUT",
+    path = "/optional/json",
+    description = "Optional tuple"
+)]
+async fn optional_tuple(_: Option<Json<(u64, String)>>) -> Json<u64> {
+    unreachable!("")
+}
+#[endpoint(method = "GET", path = "/optional/{id}", description = "Optional path")]
+async fn optional_path(_: Option<Path<u64>>) -> Json<u64> {
+    unreachable!("")
+}
+#[cfg(feature = "typed-header")]
+#[endpoint(
+    method = "GET",
+    path = "/optional/header",
+    description = "Optional header"
+)]
+async fn optional_typed_header(
+    _: Option<axum_extra::TypedHeader<axum_extra::headers::UserAgent>>,
+) -> Json<u64> {
+    unreachable!("")
+}
+
+#[test]
+fn test_optional_extractors() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(optional_json())
+        .add(optional_tuple())
+        .add(optional_path());
+    #[cfg(feature = "typed-header")]
+    let router = router.add(optional_typed_header());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let path_item = &openapi.paths.paths["/optional/json"];
+    let body = path_item
+        .post
+        .as_ref()
+        .unwrap()
+        .request_body
+        .as_ref()
+        .unwrap();
+    assert_eq!(body.required, Some(Required::False));
+    assert_eq!(
+        body.content["application/json"].schema,
+        Some(Todo::schema())
+    );
+    let body = path_item
+        .put
+        .as_ref()
+        .unwrap()
+        .request_body
+        .as_ref()
+        .unwrap();
+    assert_eq!(body.required, Some(Required::False));
+    assert!(body.content["application/json"].schema.is_some());
+
+    // Path parameters are always required
+    let operation = openapi.paths.paths["/optional/{id}"].get.as_ref().unwrap();
+    let parameters = operation.parameters.as_ref().unwrap();
+    assert_eq!(parameters[0].required, Required::True);
+
+    #[cfg(feature = "typed-header")]
+    {
+        let operation = openapi.paths.paths["/optional/header"]
+            .get
+            .as_ref()
+            .unwrap();
+        let parameters = operation.parameters.as_ref().unwrap();
+        assert_eq!(parameters[0].name, "user-agent");
+        assert_eq!(parameters[0].required, Required::False);
+    }
+
+    // Only the parameters added by the optional extractor are not required
+    let mut operation = Operation::new();
+    let mut components = Components::new();
+    <Query<QueryParams> as OperationArg>::document(&mut operation, &mut components);
+    <Option<Query<TodoFilter>> as OperationArg>::document(&mut operation, &mut components);
+    let required = operation
+        .parameters
+        .unwrap()
+        .into_iter()
+        .map(|parameter| (parameter.name, parameter.required))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        required,
+        vec![
+            ("api-key".to_string(), Required::True),
+            ("completed".to_string(), Required::False),
+        ]
+    );
+}
+
+#[derive(utoipa::IntoResponses)]
+enum TodoResponse {
+    /// The todo
+    #[response(status = 200)]
+    Found(Todo),
+    /// No todo with this id
+    #[response(status = 404)]
+    NotFound,
+    /// A todo with the same title exists
+    #[response(status = 409)]
+    Conflict(#[to_schema] ErrorBody),
+}
+impl IntoResponse for TodoResponse {
+    fn into_response(self) -> axum::response::Response {
+        unreachable!("")
+    }
+}
+#[endpoint(
+    method = "GET",
+    path = "/responses/{id}",
+    description = "Responses",
+    schemas(Todo)
+)]
+async fn todo_responses(_: Path<u64>) -> TodoResponse {
+    unreachable!("")
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/undeclared-responses/{id}",
+    description = "Responses without their schemas"
+)]
+async fn undeclared_responses(_: Path<u64>) -> TodoResponse {
+    unreachable!("")
+}
+
+#[derive(utoipa::IntoResponses)]
+enum ConflictError {
+    /// The todo was modified concurrently
+    #[response(status = 409)]
+    Conflict,
+}
+impl IntoResponse for ConflictError {
+    fn into_response(self) -> axum::response::Response {
+        unreachable!("")
+    }
+}
+#[endpoint(
+    method = "PUT",
+    path = "/responses/{id}",
+    description = "Fallible responses"
+)]
+async fn fallible_responses(_: Path<u64>) -> Result<TodoResponse, ConflictError> {
+    unreachable!("")
+}
+
+#[test]
+fn test_into_responses() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(todo_responses())
+        .add(fallible_responses());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let path_item = &openapi.paths.paths["/responses/{id}"];
+    let responses = &path_item.get.as_ref().unwrap().responses.responses;
+    assert_eq!(
+        responses.keys().collect::<Vec<_>>(),
+        vec!["200", "404", "409"]
+    );
+    let found = resolve_as_t(&responses["200"]);
+    assert_eq!(found.description, "The todo");
+    assert_eq!(
+        found.content["application/json"].schema,
+        Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name("Todo")))
+    );
+    let not_found = resolve_as_t(&responses["404"]);
+    assert_eq!(not_found.description, "No todo with this id");
+    assert!(not_found.content.is_empty());
+    let conflict = resolve_as_t(&responses["409"]);
+    assert_eq!(
+        conflict.content["application/json"].schema,
+        Some(ErrorBody::schema())
+    );
+
+    // The error responses are added to the success ones
+    let responses = &path_item.put.as_ref().unwrap().responses.responses;
+    assert_eq!(
+        responses.keys().collect::<Vec<_>>(),
+        vec!["200", "404", "409"]
+    );
+    assert_eq!(
+        resolve_as_t(&responses["409"]).description,
+        "The todo was modified concurrently"
+    );
+
+    // Schemas referenced by the responses are declared by `schemas(...)`
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert_eq!(schemas["Todo"], Todo::schema());
+    assert_no_dangling_refs(&openapi);
+
+    // or registered on the router
+    let router: OpenApiRouter = OpenApiRouter::new().schema::<TodoWithTags>();
+    let schemas = &router.openapi().components.as_ref().unwrap().schemas;
+    assert_eq!(schemas["TodoWithTags"], TodoWithTags::schema());
+    assert!(schemas.contains_key("Tag"));
+}
+
+#[endpoint(method = "POST", path = "/raw/text", description = "Text")]
+async fn raw_text(_: String) -> &'static str {
+    unreachable!("")
+}
+#[endpoint(method = "POST", path = "/raw/bytes", description = "Bytes")]
+async fn raw_bytes(_: axum::body::Bytes) -> Result<Vec<u8>, AppError> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "POST",
+    path = "/raw/csv",
+    description = "CSV",
+    content_type = "text/csv",
+    response_content_type = "application/x-ndjson"
+)]
+async fn raw_csv(_: axum::body::Body) -> axum::body::Body {
+    unreachable!("")
+}
+#[endpoint(
+    method = "POST",
+    path = "/raw/json",
+    description = "Vendor JSON",
+    content_type = "application/vnd.api+json",
+    response_content_type = "application/vnd.api+json"
+)]
+async fn vendor_json(_: Json<Todo>) -> Json<Todo> {
+    unreachable!("")
+}
+
+#[test]
+fn test_into_responses_undeclared_schemas() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(undeclared_responses());
+    assert_eq!(
+        unresolved_refs(router.openapi()),
+        ["#/components/schemas/Todo".to_string()].into()
+    );
+
+    // Registering the schema on the router resolves the reference
+    let router = router.schema::<Todo>();
+    assert_no_dangling_refs(&router.build_openapi(OpenApiBuilder::new()));
+}
+
+#[test]
+#[should_panic(expected = "unresolved references {\"#/components/schemas/Todo\"}")]
+fn test_into_responses_undeclared_schemas_docs() {
+    let _: Router = OpenApiRouter::new()
+        .add(undeclared_responses())
+        .docs(OpenApiBuilder::new(), DocsConfig::new("/openapi.json"))
+        .into_router();
+}
+
+#[test]
+fn test_raw_bodies() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(raw_text())
+        .add(raw_bytes())
+        .add(raw_csv())
+        .add(vendor_json());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let binary: RefOr<Schema> = ObjectBuilder::new()
+        .schema_type(Type::String)
+        .format(Some(SchemaFormat::KnownFormat(KnownFormat::Binary)))
+        .into();
+    let assert_bodies =
+        |path: &str, request: (&str, RefOr<Schema>), response: (&str, RefOr<Schema>)| {
+            let operation = openapi.paths.paths[path].post.as_ref().unwrap();
+            let body = operation.request_body.as_ref().unwrap();
+            assert_eq!(
+                body.content.keys().collect::<Vec<_>>(),
+                vec![request.0],
+                "{path}"
+            );
+            assert_eq!(body.content[request.0].schema, Some(request.1), "{path}");
+            let response_body = resolve_as_t(&operation.responses.responses["200"]);
+            assert_eq!(
+                response_body.content.keys().collect::<Vec<_>>(),
+                vec![response.0],
+                "{path}"
+            );
+            assert_eq!(
+                response_body.content[response.0].schema,
+                Some(response.1),
+                "{path}"
+            );
+        };
+
+    assert_bodies(
+        "/raw/text",
+        ("text/plain", String::schema()),
+        ("text/plain", String::schema()),
+    );
+    assert_bodies(
+        "/raw/bytes",
+        ("application/octet-stream", binary.clone()),
+        ("application/octet-stream", binary.clone()),
+    );
+    assert_bodies(
+        "/raw/csv",
+        ("text/csv", binary.clone()),
+        ("application/x-ndjson", binary),
+    );
+    assert_bodies(
+        "/raw/json",
+        ("application/vnd.api+json", Todo::schema()),
+        ("application/vnd.api+json", Todo::schema()),
+    );
+}
+
+#[endpoint(
+    method = "POST",
+    path = "/tuple/created",
+    description = "Created",
+    status = 201
+)]
+async fn tuple_created(Json(todo): Json<Todo>) -> (StatusCode, Json<Todo>) {
+    (StatusCode::CREATED, Json(todo))
+}
+#[endpoint(
+    method = "PUT",
+    path = "/tuple/created",
+    description = "Created with headers",
+    status = 201
+)]
+async fn tuple_headers(
+    Json(todo): Json<Todo>,
+) -> Result<(StatusCode, axum::http::HeaderMap, Json<Todo>), AppError> {
+    Ok((
+        StatusCode::CREATED,
+        axum::http::HeaderMap::new(),
+        Json(todo),
+    ))
+}
+#[endpoint(
+    method = "GET",
+    path = "/tuple/text",
+    description = "Text with headers"
+)]
+async fn tuple_text() -> ([(header::HeaderName, &'static str); 1], String) {
+    unreachable!("")
+}
+
+#[test]
+fn test_tuple_returns() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(tuple_created())
+        .add(tuple_headers())
+        .add(tuple_text());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let path_item = &openapi.paths.paths["/tuple/created"];
+    for operation in [&path_item.post, &path_item.put] {
+        let responses = &operation.as_ref().unwrap().responses.responses;
+        assert!(!responses.contains_key("200"));
+        let created = resolve_as_t(&responses["201"]);
+        assert_eq!(
+            created.content["application/json"].schema,
+            Some(Todo::schema())
+        );
+    }
+    let responses = &path_item.put.as_ref().unwrap().responses.responses;
+    assert!(responses.contains_key("404"));
+
+    let operation = openapi.paths.paths["/tuple/text"].get.as_ref().unwrap();
+    let ok = resolve_as_t(&operation.responses.responses["200"]);
+    assert_eq!(ok.content["text/plain"].schema, Some(String::schema()));
+}
+
+#[endpoint(
+    method = "POST",
+    path = "/headers/todos",
+    description = "Created with location",
+    status = 201,
+    response_headers("Location")
+)]
+async fn created_with_location(Json(todo): Json<Todo>) -> (StatusCode, Json<Todo>) {
+    (StatusCode::CREATED, Json(todo))
+}
+
+struct TotalCount(u64);
+impl axum::response::IntoResponseParts for TotalCount {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(
+        self,
+        mut res: axum::response::ResponseParts,
+    ) -> Result<axum::response::ResponseParts, Self::Error> {
+        res.headers_mut().insert("x-total-count", self.0.into());
+        Ok(res)
+    }
+}
+impl ResponseHeaders for TotalCount {
+    fn headers() -> BTreeMap<String, utoipa::openapi::Header> {
+        BTreeMap::from([(
+            "X-Total-Count".to_string(),
+            utoipa::openapi::HeaderBuilder::new()
+                .schema(u64::schema())
+                .build(),
+        )])
+    }
+}
+#[endpoint(
+    method = "GET",
+    path = "/headers/todos",
+    description = "Paginated",
+    response_headers("Link", "X-Total-Count": String)
+)]
+async fn paginated() -> Result<(TotalCount, Json<Vec<Todo>>), AppError> {
+    unreachable!("")
+}
+#[cfg(feature = "typed-header")]
+#[endpoint(method = "GET", path = "/headers/retry", description = "Rate limited")]
+async fn rate_limited() -> (
+    axum_extra::TypedHeader<axum_extra::headers::RetryAfter>,
+    Json<u64>,
+) {
+    unreachable!("")
+}
+
+#[test]
+fn test_response_headers() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(created_with_location())
+        .add(paginated());
+    #[cfg(feature = "typed-header")]
+    let router = router.add(rate_limited());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let path_item = &openapi.paths.paths["/headers/todos"];
+    let created = resolve_as_t(&path_item.post.as_ref().unwrap().responses.responses["201"]);
+    assert_eq!(created.headers.keys().collect::<Vec<_>>(), vec!["Location"]);
+    assert_eq!(created.headers["Location"].schema, String::schema());
+
+    let responses = &path_item.get.as_ref().unwrap().responses.responses;
+    let ok = resolve_as_t(&responses["200"]);
+    assert_eq!(
+        ok.headers.keys().collect::<Vec<_>>(),
+        vec!["Link", "X-Total-Count"]
+    );
+    // The headers declared by `response_headers` take precedence over the types
+    assert_eq!(ok.headers["X-Total-Count"].schema, String::schema());
+    // Error responses don't get the headers
+    assert!(resolve_as_t(&responses["404"]).headers.is_empty());
+
+    #[cfg(feature = "typed-header")]
+    {
+        let operation = openapi.paths.paths["/headers/retry"].get.as_ref().unwrap();
+        let ok = resolve_as_t(&operation.responses.responses["200"]);
+        assert!(ok.headers.contains_key("retry-after"));
+    }
+}
+
+struct RequiredApiKey;
+impl<S: Send + Sync> FromRequestParts<S> for RequiredApiKey {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(_: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+impl RejectionResponses for RequiredApiKey {
+    fn rejections() -> Vec<(StatusCode, &'static str)> {
+        vec![(StatusCode::UNAUTHORIZED, "The API key is missing")]
+    }
+}
+#[endpoint(
+    method = "PUT",
+    path = "/rejections/{id}",
+    description = "Rejections",
+    rejections = true
+)]
+async fn with_rejections(
+    _: RequiredApiKey,
+    _: Path<u64>,
+    _: Query<TodoFilter>,
+    _: Json<Todo>,
+) -> Result<Json<Todo>, AppError> {
+    unreachable!("")
+}
+#[endpoint(
+    method = "GET",
+    path = "/rejections/{id}",
+    description = "No rejections"
+)]
+async fn without_rejections(_: Path<u64>) -> Json<Todo> {
+    unreachable!("")
+}
+
+#[test]
+fn test_rejection_responses() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(with_rejections())
+        .add(without_rejections());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let path_item = &openapi.paths.paths["/rejections/{id}"];
+    let responses = &path_item.put.as_ref().unwrap().responses.responses;
+    assert_eq!(
+        responses.keys().collect::<Vec<_>>(),
+        vec!["200", "400", "401", "404", "413", "415", "422"]
+    );
+    let bad_request = resolve_as_t(&responses["400"]);
+    // The first extractor rejecting with a status describes it
+    assert_eq!(
+        bad_request.description,
+        "The path parameters don't match the expected types"
+    );
+    assert_eq!(
+        bad_request.content["text/plain"].schema,
+        Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+            "ExtractorRejection"
+        )))
+    );
+    assert_eq!(
+        resolve_as_t(&responses["401"]).description,
+        "The API key is missing"
+    );
+    // Responses documented by the handler are kept
+    assert_eq!(resolve_as_t(&responses["404"]).description, "Not found");
+
+    let responses = &path_item.get.as_ref().unwrap().responses.responses;
+    assert_eq!(responses.keys().collect::<Vec<_>>(), vec!["200"]);
+
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert!(schemas.contains_key("ExtractorRejection"));
+}
+
+#[endpoint(
+    method = "GET",
+    path = "/problems/{id}",
+    description = "Problem details"
+)]
+async fn problem(Path(id): Path<u64>) -> Result<Json<Todo>, ProblemDetails> {
+    Err(ProblemDetails::new(StatusCode::NOT_FOUND)
+        .problem_type("https://example.com/problems/not-found")
+        .detail(format!("No todo with id {id}"))
+        .extension("id", id))
+}
+
+#[tokio::test]
+async fn test_problem_details() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(problem());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let operation = openapi.paths.paths["/problems/{id}"].get.as_ref().unwrap();
+    let default = resolve_as_t(&operation.responses.responses["default"]);
+    assert_eq!(
+        default.content[PROBLEM_JSON].schema,
+        Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+            "ProblemDetails"
+        )))
+    );
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert_eq!(schemas["ProblemDetails"], ProblemDetails::schema());
+
+    let (router, _) = router.into_parts();
+    let response = router
+        .oneshot(Request::get("/problems/42").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "type": "https://example.com/problems/not-found",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "No todo with id 42",
+            "id": 42,
+        })
+    );
+    let problem: ProblemDetails = serde_json::from_value(body).unwrap();
+    assert_eq!(problem.extensions["id"], 42);
+}
+
+#[test]
+fn test_doc_comments() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(documented())
+        .add(documented_explicit());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let operation = openapi.paths.paths["/documented/{id}"]
+        .get
+        .as_ref()
+        .unwrap();
+    assert_eq!(operation.summary.as_deref(), Some("Get a todo by id"));
+    assert_eq!(
+        operation.description.as_deref(),
+        Some("Returns the todo with the given `id`:\n- `completed` is `false` for new todos")
+    );
+
+    let operation = openapi.paths.paths["/documented"].get.as_ref().unwrap();
+    assert_eq!(operation.summary.as_deref(), Some("Explicit summary"));
+    assert_eq!(
+        operation.description.as_deref(),
+        Some("Explicit description")
+    );
+}
+
+#[test]
+fn test_custom_extractors() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(custom_extractors());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    assert_endpoint(
+        &openapi.paths,
+        "/custom-extractors",
+        "post",
+        "custom_extractors",
+        "",
+        Some(Todo::schema()),
+        Some(Todo::schema()),
+        None,
+        None,
+    );
+    let operation = openapi.paths.paths["/custom-extractors"]
+        .post
+        .as_ref()
+        .unwrap();
+    assert_eq!(
+        operation.security,
+        Some(vec![SecurityRequirement::new(
+            "bearer",
+            Vec::<String>::new()
+        )])
+    );
+    let responses: Vec<_> = operation.responses.responses.keys().collect();
+    assert_eq!(responses, vec!["200", "401"]);
+}
+
+#[test]
+fn test_generic_tuple_and_array_types() {
+    let router: OpenApiRouter = OpenApiRouter::new()
+        .add(map())
+        .add(tuple())
+        .add(nested_tuple())
+        .add(unit());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    assert_endpoint(
+        &openapi.paths,
+        "/map",
+        "post",
+        "map",
+        "Map",
+        Some(BTreeMap::<String, Vec<Todo>>::schema()),
+        Some(HashMap::<String, u64>::schema()),
+        None,
+        None,
+    );
+
+    let tuple_schema: RefOr<Schema> = ArrayBuilder::new()
+        .items(ArrayItems::False)
+        .prefix_items([
+            resolve_as_t(&u64::schema()).clone(),
+            resolve_as_t(&String::schema()).clone(),
+        ])
+        .min_items(Some(2))
+        .max_items(Some(2))
+        .into();
+    let array_schema: RefOr<Schema> = ArrayBuilder::new()
+        .items(u8::schema())
+        .min_items(Some(32))
+        .max_items(Some(32))
+        .into();
+    assert_endpoint(
+        &openapi.paths,
+        "/tuple",
+        "post",
+        "tuple",
+        "Tuple",
+        Some(array_schema),
+        Some(tuple_schema.clone()),
+        None,
+        None,
+    );
+
+    // Tuples and arrays nested in `Option`, `Vec` and `Box`
+    let tags_schema: RefOr<Schema> = ArrayBuilder::new()
+        .items(Tag::schema())
+        .min_items(Some(2))
+        .max_items(Some(2))
+        .into();
+    let option_schema: RefOr<Schema> = OneOfBuilder::new()
+        .item(ObjectBuilder::new().schema_type(Type::Null))
+        .item(
+            ArrayBuilder::new()
+                .items(ArrayItems::False)
+                .prefix_items([
+                    resolve_as_t(&u64::schema()).clone(),
+                    resolve_as_t(&tags_schema).clone(),
+                ])
+                .min_items(Some(2))
+                .max_items(Some(2)),
+        )
+        .into();
+    let vec_schema: RefOr<Schema> = ArrayBuilder::new().items(tuple_schema).into();
+    assert_endpoint(
+        &openapi.paths,
+        "/nested_tuple",
+        "post",
+        "nested_tuple",
+        "Nested tuple",
+        Some(option_schema),
+        Some(vec_schema),
+        None,
+        None,
+    );
+
+    let operation = openapi.paths.paths["/unit"].post.as_ref().unwrap();
+    let response = resolve_as_t(&operation.responses.responses["200"]);
+    assert_eq!(
+        response.content["application/json"].schema,
+        Some(<()>::schema())
+    );
+    assert!(operation.request_body.is_some());
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert!(schemas.contains_key("Tag"));
+}
+
+#[test]
+fn test_qualified_and_annotated_extractors() {
+    let router: OpenApiRouter<Arc<MyState>> =
+        OpenApiRouter::new().add(qualified()).add(annotated());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let id = ParameterBuilder::new()
+        .parameter_in(ParameterIn::Path)
+        .name("id")
+        .required(Required::True)
+        .schema(Some(u64::schema()))
+        .build();
+    assert_endpoint(
+        &openapi.paths,
+        "/qualified/{id}",
+        "post",
+        "qualified",
+        "Qualified",
+        Some(Todo::schema()),
+        Some(Todo::schema()),
+        Some(TodoFilter::into_params(|| Some(ParameterIn::Query))),
+        Some(vec![id.clone()]),
+    );
+    let operation = openapi.paths.paths["/qualified/{id}"]
+        .post
+        .as_ref()
+        .unwrap();
+    let responses: Vec<_> = operation.responses.responses.keys().collect();
+    assert_eq!(responses, vec!["200", "404"]);
+
+    assert_endpoint(
+        &openapi.paths,
+        "/annotated/{id}",
+        "post",
+        "annotated",
+        "Annotated",
+        Some(Todo::schema()),
+        Some(Todo::schema()),
+        None,
+        Some(vec![id]),
+    );
+}
+
+#[endpoint(method = "GET", path = "/files/{*path}", description = "Wildcard")]
+async fn get_file(Path(path): Path<String>) -> String {
+    path
+}
+
+#[tokio::test]
+async fn test_wildcard_path() {
+    let endpoint = get_file();
+    assert_eq!(endpoint.path, "/files/{*path}");
+
+    let router: OpenApiRouter = OpenApiRouter::new().add(endpoint);
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+    let paths: Vec<_> = openapi.paths.paths.keys().collect();
+    assert_eq!(paths, vec!["/files/{path}"]);
+    let operation = openapi.paths.paths["/files/{path}"].get.as_ref().unwrap();
+    assert_eq!(
+        operation.parameters,
+        Some(vec![ParameterBuilder::new()
+            .parameter_in(ParameterIn::Path)
+            .name("path")
+            .required(Required::True)
+            .schema(Some(String::schema()))
+            .build()])
+    );
+
+    let (_, body) = get(&router.into_router(), "/files/a/b.txt").await;
+    assert_eq!(body, "a/b.txt");
+}
+
+#[test]
+fn test_tuple_and_struct_path() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(tuple_path()).add(struct_path());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let expected_path_params = vec![
+        ParameterBuilder::new()
+            .parameter_in(ParameterIn::Path)
+            .name("org")
+            .required(Required::True)
+            .schema(Some(u64::schema()))
+            .build(),
+        ParameterBuilder::new()
+            .parameter_in(ParameterIn::Path)
+            .name("user")
+            .required(Required::True)
+            .schema(Some(String::schema()))
+            .build(),
+    ];
+    assert_endpoint(
+        &openapi.paths,
+        "/orgs/{org}/users/{user}",
+        "get",
+        "tuple_path",
+        "Tuple path",
+        Some(Todo::schema()),
+        None,
+        None,
+        Some(expected_path_params),
+    );
+
+    assert_endpoint(
+        &openapi.paths,
+        "/orgs/{org}/members/{user}",
+        "get",
+        "struct_path",
+        "Struct path",
+        Some(Todo::schema()),
+        None,
+        None,
+        Some(UserParams::into_params(|| Some(ParameterIn::Path))),
+    );
+}
+
+#[test]
+fn test_into_params_schemas() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(issues()).add(issue());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let kind = |path: &str| {
+        let operation = openapi.paths.paths[path].get.as_ref().unwrap();
+        let parameters = operation.parameters.as_ref().unwrap();
+        let kind = parameters
+            .iter()
+            .find(|param| param.name == "kind")
+            .unwrap();
+        kind.schema.clone()
+    };
+    let reference = Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+        "IssueKind",
+    )));
+    assert_eq!(kind("/issues"), reference);
+    assert_eq!(kind("/issues/{kind}/{id}"), reference);
+
+    // The schemas referenced by the fields are declared by `schemas(...)`
+    let schemas = &openapi.components.as_ref().unwrap().schemas;
+    assert_eq!(schemas["IssueKind"], IssueKind::schema());
+    assert_no_dangling_refs(&openapi);
+}
+
+#[test]
+fn test_into_params_undeclared_schemas() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(undeclared_issues());
+    assert_eq!(
+        unresolved_refs(router.openapi()),
+        ["#/components/schemas/IssueKind".to_string()].into()
+    );
+}
+
+#[test]
+#[should_panic(expected = "unresolved references {\"#/components/schemas/IssueKind\"}")]
+fn test_into_params_undeclared_schemas_build() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(undeclared_issues());
+    router.build_openapi(OpenApiBuilder::new());
+}
+
+#[test]
+#[should_panic(expected = "has no property for the placeholder `{slug}`")]
+fn test_unknown_placeholder_path() {
+    unknown_placeholder_path();
+}
+
+#[tokio::test]
+async fn test_multiple_placeholders_path() {
+    let router: OpenApiRouter = OpenApiRouter::new().add(map_path()).add(schema_path());
+    let openapi = router.build_openapi(OpenApiBuilder::new());
+
+    let path_params = |path: &str| {
+        let operation = openapi.paths.paths[path].get.as_ref().unwrap();
+        operation.parameters.clone()
+    };
+    let param = |name: &str, schema: RefOr<Schema>| {
+        ParameterBuilder::new()
+            .parameter_in(ParameterIn::Path)
+            .name(name)
+            .required(Required::True)
+            .schema(Some(schema))
+            .build()
+    };
+
+    // Every placeholder is documented: with the schema of the map values...
+    assert_eq!(
+        path_params("/orgs/{org}/teams/{team}"),
+        Some(vec![
+            param("org", String::schema()),
+            param("team", String::schema())
+        ])
+    );
+    // ...or of the struct fields
+    assert_eq!(
+        path_params("/orgs/{org}/projects/{project}"),
+        Some(vec![
+            param("org", u64::schema()),
+            param("project", String::schema())
+        ])
+    );
+
+    let (_, body) = get(&router.into_router(), "/orgs/acme/teams/core").await;
+    assert_eq!(body, "acme/core");
+}
+
+#[tokio::test]
+async fn test_docs() {
+    let config = DocsConfig::new("/openapi.json");
+    #[cfg(feature = "swagger-ui")]
+    let config = config.swagger_ui("/swagger-ui");
+    #[cfg(feature = "redoc")]
+    let config = config.redoc("/redoc");
+    #[cfg(feature = "scalar")]
+    let config = config.scalar("/scalar");
+
+    let docs: OpenApiRouter = OpenApiRouter::new()
+        .add(get_todos())
+        .docs(OpenApiBuilder::new(), config)
+        .add(insert_todo());
+    let router: Router = OpenApiRouter::new()
+        .add(mark_todo_as_complete())
+        .merge(docs)
+        .into_router();
+
+    let (content_type, body) = get(&router, "/openapi.json").await;
+    assert_eq!(content_type, "application/json");
+    let openapi: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert!(openapi["paths"]["/todos"]["get"].is_object());
+    assert!(openapi["paths"]["/openapi.json"].is_null());
+    // The endpoints added after `docs`, also by the parent router, are served
+    assert!(openapi["paths"]["/todos"]["post"].is_object());
+    assert!(openapi["paths"]["/todos"]["patch"].is_object());
+
+    #[cfg(feature = "swagger-ui")]
+    {
+        let (content_type, body) = get(&router, "/swagger-ui").await;
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert!(body.contains("/openapi.json"));
+        let (content_type, _) = get(&router, "/swagger-ui/swagger-ui-bundle.js").await;
+        assert_eq!(content_type, "text/javascript");
+        let (content_type, _) = get(&router, "/swagger-ui/swagger-ui.css").await;
+        assert_eq!(content_type, "text/css");
+    }
+    #[cfg(feature = "redoc")]
+    {
+        let (content_type, body) = get(&router, "/redoc").await;
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert!(body.contains("/openapi.json"));
+        let (content_type, _) = get(&router, "/redoc/redoc.standalone.js").await;
+        assert_eq!(content_type, "text/javascript");
+    }
+    #[cfg(feature = "scalar")]
+    {
+        let (content_type, body) = get(&router, "/scalar").await;
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert!(body.contains("/openapi.json"));
+        let (content_type, _) = get(&router, "/scalar/scalar.min.js").await;
+        assert_eq!(content_type, "text/javascript");
+    }
+}
+
+#[tokio::test]
+async fn test_spec_format() {
+    let openapi = OpenApiRouter::new()
+        .add(get_todos())
+        .build_openapi(OpenApiBuilder::new());
+    let router: Router = Router::new().route("/openapi", spec_handler(&openapi));
+
+    let (content_type, body) = get(&router, "/openapi").await;
+    assert_eq!(content_type, "application/json");
+    assert_eq!(body, openapi.to_json().unwrap());
+
+    let (content_type, _) = get(&router, "/openapi?format=json").await;
+    assert_eq!(content_type, "application/json");
+    let (content_type, _) = get_accept(&router, "/openapi", "text/html, */*;q=0.8").await;
+    assert_eq!(content_type, "application/json");
+
+    #[cfg(feature = "yaml")]
+    {
+        let (content_type, body) = get(&router, "/openapi?format=yaml").await;
+        assert_eq!(content_type, "application/yaml");
+        assert_eq!(body, openapi.to_yaml().unwrap());
+
+        let (content_type, _) = get_accept(&router, "/openapi", "application/yaml").await;
+        assert_eq!(content_type, "application/yaml");
+        let (content_type, _) = get_accept(
+            &router,
+            "/openapi",
+            "application/json;q=0.5, application/x-yaml",
+        )
+        .await;
+        assert_eq!(content_type, "application/yaml");
+        // The query parameter takes precedence over the `Accept` header.
+        let (content_type, _) =
+            get_accept(&router, "/openapi?format=json", "application/yaml").await;
+        assert_eq!(content_type, "application/json");
+    }
+    #[cfg(not(feature = "yaml"))]
+    {
+        let (content_type, _) = get(&router, "/openapi?format=yaml").await;
+        assert_eq!(content_type, "application/json");
+    }
+}
+
+#[tokio::test]
+async fn test_spec_etag() {
+    let openapi = OpenApiRouter::new()
+        .add(get_todos())
+        .build_openapi(OpenApiBuilder::new());
+    let snapshot = OpenApiSnapshot::new(openapi.clone());
+    let router: Router = Router::new().route("/openapi", spec_handler(&openapi));
+
+    let response = router
+        .clone()
+        .oneshot(Request::get("/openapi").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::VARY], "accept");
+    let etag = response.headers()[header::ETAG].clone();
+    assert_eq!(&etag, snapshot.etag(SpecFormat::Json));
+    assert!(etag.to_str().unwrap().starts_with('"'));
+
+    for if_none_match in [
+        etag.to_str().unwrap().to_string(),
+        format!("W/{}", etag.to_str().unwrap()),
+        format!("\"other\", {}", etag.to_str().unwrap()),
+        "*".to_string(),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::get("/openapi")
+                    .header(header::IF_NONE_MATCH, &if_none_match)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_MODIFIED,
+            "{if_none_match}"
+        );
+        assert_eq!(response.headers()[header::ETAG], etag);
+        assert_eq!(response.headers()[header::VARY], "accept");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::get("/openapi")
+                .header(header::IF_NONE_MATCH, "\"other\"")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    #[cfg(feature = "yaml")]
+    assert_ne!(snapshot.etag(SpecFormat::Yaml), &etag);
+}
+
+/// Every `$ref` points to a component.
+fn assert_no_dangling_refs(openapi: &utoipa::openapi::OpenApi) {
+    let refs = unresolved_refs(openapi);
+    assert!(refs.is_empty(), "dangling references {refs:?}");
+}
+
+async fn get(router: &Router, uri: &str) -> (String, String) {
+    send(router, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+async fn get_accept(router: &Router, uri: &str, accept: &str) -> (String, String) {
+    let request = Request::get(uri)
+        .header(header::ACCEPT, accept)
+        .body(Body::empty())
+        .unwrap();
+    send(router, request).await
+}
+
+async fn send(router: &Router, request: Request<Body>) -> (String, String) {
+    let uri = request.uri().clone();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK, "{uri}");
+    let content_type = response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (content_type, String::from_utf8(body.to_vec()).unwrap())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -0,0 +1,17 @@
+#![allow(unused_imports)]
+
+use axum::Json;
+use axum_openapi3::endpoint;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct NotASchema {
+    title: String,
+}
+
+#[endpoint(method = "POST", path = "/todos")]
+async fn insert_todo(_: Json<NotASchema>) -> Json<u64> {
+    unreachable!()
+}
+
+fn main() {}
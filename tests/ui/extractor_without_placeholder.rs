@@ -0,0 +1,11 @@
+#![allow(unused_imports)]
+
+use axum::{extract::Path, Json};
+use axum_openapi3::endpoint;
+
+#[endpoint(method = "GET", path = "/todos")]
+async fn get_todo(Path(id): Path<u64>) -> Json<u64> {
+    Json(id)
+}
+
+fn main() {}
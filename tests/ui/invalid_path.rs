@@ -0,0 +1,16 @@
+#![allow(unused_imports)]
+
+use axum::Json;
+use axum_openapi3::endpoint;
+
+#[endpoint(method = "GET", path = "/todos/:id")]
+async fn legacy() -> Json<u64> {
+    unreachable!()
+}
+
+#[endpoint(method = "GET", path = "/todos/{id}", path_prefix = "/todos/{id}")]
+async fn duplicated_prefix() -> Json<u64> {
+    unreachable!()
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+#![allow(unused_imports)]
+
+use axum::Json;
+use axum_openapi3::endpoint;
+
+#[endpoint(method = "GET", path = "/todos/{id}")]
+async fn get_todo() -> Json<u64> {
+    unreachable!()
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+#![allow(unused_imports)]
+
+use axum::{extract::Path, Json};
+use axum_openapi3::endpoint;
+
+#[endpoint(method = "GET", path = "/orgs/{org_id}/todos/{id}")]
+async fn get_todo(Path(id): Path<(u64,)>) -> Json<u64> {
+    Json(id.0)
+}
+
+#[endpoint(method = "GET", path = "/todos/{id}")]
+async fn get_org_todo(Path((_, id)): Path<(String, u64)>) -> Json<u64> {
+    Json(id)
+}
+
+fn main() {}
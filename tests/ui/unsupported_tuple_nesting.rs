@@ -0,0 +1,13 @@
+#![allow(unused_imports)]
+
+use std::collections::HashMap;
+
+use axum::Json;
+use axum_openapi3::endpoint;
+
+#[endpoint(method = "POST", path = "/pairs")]
+async fn pairs(_: Json<HashMap<String, (u64, String)>>) -> Json<u64> {
+    unreachable!()
+}
+
+fn main() {}